@@ -0,0 +1,138 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Derive `Store` for a struct with named fields. This generates a handle struct named
+/// `<Name>Store`, which holds one `Mutable` per field, so that readers only depend on the fields
+/// they read. The handle has an accessor for each field, and `get` and `set` methods for reading
+/// and writing the struct as a whole. `get` can only be called if every field is `Clone`, and
+/// `set` if every field is `PartialEq`; other fields don't prevent the derive from compiling.
+#[proc_macro_derive(Store)]
+pub fn derive_store(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_store(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_store(input: DeriveInput) -> Result<TokenStream2, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Store cannot be derived for generic structs",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Store can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Store can only be derived for structs with named fields",
+        ));
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let handle = format_ident!("{}Store", name);
+    let names: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+    let types: Vec<_> = fields.named.iter().map(|f| &f.ty).collect();
+    let handle_doc = format!(" Per-field reactive store for [`{name}`].");
+    let accessor_docs = names
+        .iter()
+        .map(|field| format!(" The mutable which holds `{}`.", quote!(#field)));
+    // The bounds are higher-ranked so that they aren't checked when the handle is defined, only
+    // when the method is called. A plain bound on a concrete type which doesn't hold is an error.
+    let clone_bounds = quote! { #(for<'__store> #types: ::core::clone::Clone,)* };
+    let eq_bounds = quote! { #(for<'__store> #types: ::core::cmp::PartialEq,)* };
+
+    Ok(quote! {
+        #[doc = #handle_doc]
+        #vis struct #handle {
+            #(#names: ::do_you_believe::Mutable<#types>,)*
+        }
+
+        impl ::core::marker::Copy for #handle {}
+        impl ::core::clone::Clone for #handle {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl #handle {
+            #(
+                #[doc = #accessor_docs]
+                #vis fn #names(&self) -> ::do_you_believe::Mutable<#types> {
+                    self.#names
+                }
+            )*
+
+            /// Read the whole struct, or return an error if any field no longer exists.
+            #vis fn try_get<R: ::do_you_believe::ReadMutable>(
+                &self,
+                cx: &R,
+            ) -> ::core::result::Result<#name, ::do_you_believe::MutableError>
+            where
+                #clone_bounds
+            {
+                ::core::result::Result::Ok(#name {
+                    #(#names: self.#names.try_get_clone(cx)?,)*
+                })
+            }
+
+            /// Read the whole struct.
+            #vis fn get<R: ::do_you_believe::ReadMutable>(&self, cx: &R) -> #name
+            where
+                #clone_bounds
+            {
+                self.try_get(cx).unwrap()
+            }
+
+            /// Write the whole struct in a single transaction, or return an error if any field
+            /// no longer exists. Only the fields which differ are changed.
+            #vis fn try_set<W: ::do_you_believe::ReadMutable + ::do_you_believe::WriteMutable>(
+                &self,
+                cx: &mut W,
+                value: #name,
+            ) -> ::core::result::Result<(), ::do_you_believe::MutableError>
+            where
+                #eq_bounds
+            {
+                let #name { #(#names,)* } = value;
+                ::do_you_believe::WriteMutable::transaction(cx, |tx| {
+                    #(tx.set(self.#names, #names);)*
+                })
+            }
+
+            /// Write the whole struct. Only the fields which differ are changed.
+            #vis fn set<W: ::do_you_believe::ReadMutable + ::do_you_believe::WriteMutable>(
+                &self,
+                cx: &mut W,
+                value: #name,
+            )
+            where
+                #eq_bounds
+            {
+                self.try_set(cx, value).unwrap();
+            }
+        }
+
+        impl ::do_you_believe::Store for #name {
+            type Handle = #handle;
+
+            fn into_store<C: ::do_you_believe::CreateMutable + ?Sized>(
+                self,
+                cx: &mut C,
+            ) -> #handle {
+                let #name { #(#names,)* } = self;
+                #handle {
+                    #(#names: cx.create_mutable(#names),)*
+                }
+            }
+        }
+    })
+}
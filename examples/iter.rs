@@ -61,9 +61,9 @@ fn setup_view_root(mut commands: Commands) {
                             ..default()
                         })
                         .with_effect(
-                            move |world: DeferredWorld| match selected.as_ref(&world) {
-                                Some(s) => *s == suit3,
-                                None => false,
+                            move |world: DeferredWorld| match selected.try_as_ref(&world) {
+                                Ok(Some(s)) => *s == suit3,
+                                _ => false,
                             },
                             |selected, entity| {
                                 entity.entry::<BorderColor>().and_modify(|mut border| {
@@ -77,7 +77,7 @@ fn setup_view_root(mut commands: Commands) {
                         )
                         .observe(
                             move |_trigger: Trigger<Pointer<Down>>, mut world: DeferredWorld| {
-                                selected.set_clone(&mut world, Some(suit.clone()));
+                                let _ = selected.try_set(&mut world, Some(suit.clone()));
                             },
                         )
                         .with_children(|builder| {
@@ -0,0 +1,181 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    ecs::reflect::ReflectComponent,
+    prelude::*,
+    reflect::{GetPath, TypeRegistry},
+};
+
+use crate::{EntityWithEffect, Mutable};
+
+/// Accesses a value stored on an entity, usually a field of a component. Used by
+/// [`EntityBind::bind`].
+///
+/// Lenses can be constructed from a getter and setter pair with [`ComponentLens::new`], or from
+/// a reflection path such as `"Node.width"`, where the first segment is the short type name of a
+/// registered component.
+pub trait Lens<T>: Send + Sync + 'static {
+    /// Read the value from the entity, or `None` if it is not present.
+    fn get(&self, world: &World, entity: Entity) -> Option<T>;
+
+    /// Write the value to the entity.
+    fn set(&self, entity: &mut EntityWorldMut, value: T);
+}
+
+/// A [`Lens`] which accesses a component using a getter and setter pair.
+pub struct ComponentLens<C, GetFn, SetFn> {
+    get: GetFn,
+    set: SetFn,
+    marker: std::marker::PhantomData<fn(&C)>,
+}
+
+impl<C, GetFn, SetFn> ComponentLens<C, GetFn, SetFn> {
+    /// Constructs a new lens.
+    ///
+    /// Arguments:
+    /// * `get`: Reads the value from the component.
+    /// * `set`: Writes the value to the component.
+    pub fn new(get: GetFn, set: SetFn) -> Self {
+        Self {
+            get,
+            set,
+            marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<
+        T,
+        C: Component,
+        GetFn: Fn(&C) -> T + Send + Sync + 'static,
+        SetFn: Fn(&mut C, T) + Send + Sync + 'static,
+    > Lens<T> for ComponentLens<C, GetFn, SetFn>
+{
+    fn get(&self, world: &World, entity: Entity) -> Option<T> {
+        world.get::<C>(entity).map(&self.get)
+    }
+
+    fn set(&self, entity: &mut EntityWorldMut, value: T) {
+        if let Some(mut component) = entity.get_mut::<C>() {
+            (self.set)(&mut component, value);
+        }
+    }
+}
+
+/// Split a reflection path into the component type name and the path within the component.
+fn split_path(path: &str) -> (&str, &str) {
+    match path.split_once('.') {
+        Some((component, field)) => (component, field),
+        None => (path, ""),
+    }
+}
+
+/// Look up the reflected component data for a type name.
+fn reflect_component<'r>(registry: &'r TypeRegistry, name: &str) -> Option<&'r ReflectComponent> {
+    registry
+        .get_with_short_type_path(name)
+        .or_else(|| registry.get_with_type_path(name))?
+        .data::<ReflectComponent>()
+}
+
+impl<T: FromReflect + PartialReflect> Lens<T> for &'static str {
+    fn get(&self, world: &World, entity: Entity) -> Option<T> {
+        let (component, field) = split_path(self);
+        let registry = world.get_resource::<AppTypeRegistry>()?.read();
+        let reflected =
+            reflect_component(&registry, component)?.reflect(world.get_entity(entity).ok()?)?;
+        T::from_reflect(reflected.reflect_path(field).ok()?)
+    }
+
+    fn set(&self, entity: &mut EntityWorldMut, value: T) {
+        let (component, field) = split_path(self);
+        let Some(registry) = entity.world().get_resource::<AppTypeRegistry>().cloned() else {
+            return;
+        };
+        let registry = registry.read();
+        let Some(reflect) = reflect_component(&registry, component) else {
+            return;
+        };
+        if let Some(mut reflected) = reflect.reflect_mut(entity) {
+            if let Ok(target) = reflected.reflect_path_mut(field) {
+                if target.try_apply(&value).is_err() {
+                    warn!("Cannot apply value to {}", self);
+                }
+            }
+        }
+    }
+}
+
+/// Which way a binding copies values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindDirection {
+    /// Copy the mutable to the entity.
+    ToEntity,
+    /// Copy the entity to the mutable.
+    ToMutable,
+    /// Copy changes in either direction. If both change at once, the mutable wins.
+    Both,
+}
+
+/// Trait for binding a [`Mutable`] to a value on an entity.
+pub trait EntityBind {
+    /// Keep a value on this entity in sync with a [`Mutable`]. Each side is only written when the
+    /// other side changes to a value which differs from the last synced value, so two-way
+    /// bindings don't loop.
+    ///
+    /// Arguments:
+    /// * `mutable`: The mutable to bind.
+    /// * `lens`: Accesses the value on the entity.
+    /// * `direction`: Which way changes are copied.
+    fn bind<T: PartialEq + Clone + Send + Sync + 'static, L: Lens<T>>(
+        &mut self,
+        mutable: Mutable<T>,
+        lens: L,
+        direction: BindDirection,
+    ) -> &mut Self;
+}
+
+impl EntityBind for EntityCommands<'_> {
+    fn bind<T: PartialEq + Clone + Send + Sync + 'static, L: Lens<T>>(
+        &mut self,
+        mutable: Mutable<T>,
+        lens: L,
+        direction: BindDirection,
+    ) -> &mut Self {
+        let target = self.id();
+        let lens = Arc::new(lens);
+        let deps_lens = lens.clone();
+        // The last value which was copied from one side to the other.
+        let synced: Mutex<Option<T>> = Mutex::new(None);
+        self.with_effect(
+            move |world: &World| {
+                (
+                    mutable.try_get_clone(world).ok(),
+                    deps_lens.get(world, target),
+                )
+            },
+            move |(value, current): (Option<T>, Option<T>), entity| {
+                let mut synced = synced.lock().unwrap();
+                match (value, current) {
+                    (Some(value), _)
+                        if direction != BindDirection::ToMutable
+                            && synced.as_ref() != Some(&value) =>
+                    {
+                        lens.set(entity, value.clone());
+                        *synced = Some(value);
+                    }
+                    (_, Some(current))
+                        if direction != BindDirection::ToEntity
+                            && synced.as_ref() != Some(&current) =>
+                    {
+                        entity.world_scope(|world| {
+                            let _ = mutable.try_set(world, current.clone());
+                        });
+                        *synced = Some(current);
+                    }
+                    _ => {}
+                }
+            },
+        )
+    }
+}
@@ -1,14 +1,15 @@
 use bevy::{
     ecs::component::{ComponentHooks, StorageType},
     prelude::{
-        BuildChildren, Bundle, ChildBuild, Command, Component, Entity, EntityCommands,
-        EntityWorldMut,
+        BuildChildren, Bundle, ChildBuild, ChildBuilder, Command, Component, Entity,
+        EntityCommands, EntityWorldMut,
     },
 };
 
 pub trait ChildTuple {
     fn create(self, entity: &mut EntityWorldMut);
     fn create_commands(self, entity: &mut EntityCommands);
+    fn create_children(self, builder: &mut ChildBuilder);
 }
 
 macro_rules! impl_child_tuple {
@@ -31,6 +32,12 @@ macro_rules! impl_child_tuple {
                     )*
                 });
             }
+
+            fn create_children(self: Self, builder: &mut ChildBuilder) {
+                $(
+                    builder.spawn(self.$idx);
+                )*
+            }
         }
     };
 }
@@ -38,6 +45,7 @@ macro_rules! impl_child_tuple {
 impl ChildTuple for () {
     fn create(self, _: &mut EntityWorldMut) {}
     fn create_commands(self, _: &mut EntityCommands) {}
+    fn create_children(self, _: &mut ChildBuilder) {}
 }
 
 impl_child_tuple!(B0, 0);
@@ -115,3 +123,9 @@ impl BuildChildrenFn for EntityCommands<'_> {
         children.create_commands(self);
     }
 }
+
+impl BuildChildrenFn for ChildBuilder<'_> {
+    fn children<C: ChildTuple>(&mut self, children: C) {
+        children.create_children(self);
+    }
+}
@@ -0,0 +1,51 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{CreateMemo, Memo};
+
+/// Component which provides a context value of type `T` to all of the descendants of an entity.
+/// Descendants look up the value with [`Contexts`] or [`UseContext::use_context`], which return
+/// the value from the nearest ancestor that provides one, including ancestors that are ghost
+/// nodes.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Provide<T: Send + Sync + 'static>(pub T);
+
+/// System parameter which looks up context values of type `T` provided by [`Provide`]. Use this
+/// in a deps system to re-run an effect or memo when the context changes.
+#[derive(SystemParam)]
+pub struct Contexts<'w, 's, T: Send + Sync + 'static> {
+    providers: Query<'w, 's, &'static Provide<T>>,
+    parents: Query<'w, 's, &'static Parent>,
+}
+
+impl<T: Send + Sync + 'static> Contexts<'_, '_, T> {
+    /// Find the entity which provides the context for `entity`: either `entity` itself, or its
+    /// nearest ancestor with a [`Provide<T>`] component.
+    pub fn provider(&self, entity: Entity) -> Option<Entity> {
+        std::iter::once(entity)
+            .chain(self.parents.iter_ancestors(entity))
+            .find(|ancestor| self.providers.contains(*ancestor))
+    }
+
+    /// Get the context value for `entity`, or `None` if there is no provider.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let provider = self.provider(entity)?;
+        self.providers.get(provider).ok().map(|provide| &provide.0)
+    }
+}
+
+/// Trait for reading context values while building children.
+pub trait UseContext {
+    /// Create a [`Memo`] which tracks the context value of type `T` provided to the parent
+    /// entity. The memo is `None` if there is no provider.
+    fn use_context<T: PartialEq + Clone + Send + Sync + 'static>(&mut self) -> Memo<Option<T>>;
+}
+
+impl UseContext for ChildBuilder<'_> {
+    fn use_context<T: PartialEq + Clone + Send + Sync + 'static>(&mut self) -> Memo<Option<T>> {
+        let parent = self.parent_entity();
+        self.create_memo(
+            move |contexts: Contexts<T>| contexts.get(parent).cloned(),
+            |value| value,
+        )
+    }
+}
@@ -22,6 +22,8 @@ pub struct EffectPlugin;
 impl Plugin for EffectPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, update_effects);
+        #[cfg(debug_assertions)]
+        app.add_systems(Last, crate::mutable::report_orphaned_mutables);
         app.world_mut()
             .register_component_hooks::<EffectCell>()
             .on_remove(|mut world, entity, _cond| {
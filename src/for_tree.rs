@@ -0,0 +1,299 @@
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    ecs::{system::SystemId, world::DeferredWorld},
+    prelude::*,
+    ui::experimental::GhostNode,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    effect::{AnyEffect, EffectCell, UnregisterSystemCommand},
+    mutable::ReadMutable,
+    reconcile::{reconcile, Reconciler},
+    CreateMutableIn, Mutable, MutableScope,
+};
+
+/// Information about a node in a [`ForTree`], passed to the node builder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TreeNode {
+    /// The depth of the node, where root nodes have a depth of zero.
+    pub depth: usize,
+
+    /// Whether the node is expanded. Setting this to true will build the node's children.
+    pub expanded: Mutable<bool>,
+}
+
+/// Control-flow node which renders hierarchical data. Each level of the tree is reconciled in the
+/// same manner as [`crate::For`], with items matched by key. Expanded state is also tracked by
+/// key, so it is preserved when a node is rebuilt, and as long as the key remains in the tree.
+pub struct ForTree<
+    M,
+    Item,
+    Key,
+    ItemIter,
+    RootsFn: IntoSystem<(), ItemIter, M>,
+    KeyFn,
+    ChildrenFn,
+    EachFn,
+> {
+    roots_fn: Option<RootsFn>,
+    roots_sys: Option<SystemId<(), ItemIter>>,
+    key: KeyFn,
+    children: ChildrenFn,
+    each: EachFn,
+    default_expanded: bool,
+    expanded: HashMap<Key, Mutable<bool>>,
+    /// Entity which owns the expanded state. This can't be the tree node itself, since its
+    /// children are replaced on every update.
+    owner: Option<Entity>,
+    state: Vec<TreeItem<Item, Key>>,
+    marker: std::marker::PhantomData<M>,
+}
+
+/// View state for a single node in the tree.
+struct TreeItem<Item, Key> {
+    /// Ghost node containing both the content and the children of this node.
+    child: Entity,
+    /// Ghost node containing the children of this node.
+    container: Entity,
+    key: Key,
+    item: Item,
+    children: Vec<TreeItem<Item, Key>>,
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + Send + Sync + 'static,
+        Key: Hash + Eq + Clone + Send + Sync + 'static,
+        ItemIter: Iterator<Item = Item> + 'static,
+        RootsFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        KeyFn: Fn(&Item) -> Key + Send + Sync + 'static,
+        ChildrenFn: Fn(&Item) -> Vec<Item> + Send + Sync + 'static,
+        EachFn: Fn(&Item, TreeNode, &mut ChildBuilder) + Send + Sync + 'static,
+    > ForTree<M, Item, Key, ItemIter, RootsFn, KeyFn, ChildrenFn, EachFn>
+{
+    /// Constructs a new tree node.
+    ///
+    /// Arguments:
+    /// * `roots_fn`: System which returns the root items.
+    /// * `key`: Returns a key which identifies an item.
+    /// * `children`: Returns the children of an item.
+    /// * `each`: Builds the content for an item.
+    pub fn new(roots_fn: RootsFn, key: KeyFn, children: ChildrenFn, each: EachFn) -> Self {
+        Self {
+            roots_fn: Some(roots_fn),
+            roots_sys: None,
+            key,
+            children,
+            each,
+            default_expanded: false,
+            expanded: HashMap::default(),
+            owner: None,
+            state: Vec::new(),
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets whether nodes are initially expanded.
+    pub fn expanded(mut self, expanded: bool) -> Self {
+        self.default_expanded = expanded;
+        self
+    }
+
+    pub fn build(self) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(self)))
+    }
+
+    /// Collect the keys of every item in the tree, whether or not it is visible.
+    fn collect_keys(&self, items: &[Item], keys: &mut HashSet<Key>) {
+        for item in items {
+            keys.insert((self.key)(item));
+            self.collect_keys(&(self.children)(item), keys);
+        }
+    }
+}
+
+/// Reconciles one level of the tree.
+struct TreeLevel<
+    'a,
+    M,
+    Item,
+    Key,
+    ItemIter,
+    RootsFn: IntoSystem<(), ItemIter, M>,
+    KeyFn,
+    ChildrenFn,
+    EachFn,
+> {
+    tree: &'a mut ForTree<M, Item, Key, ItemIter, RootsFn, KeyFn, ChildrenFn, EachFn>,
+    depth: usize,
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + Send + Sync + 'static,
+        Key: Hash + Eq + Clone + Send + Sync + 'static,
+        ItemIter: Iterator<Item = Item> + 'static,
+        RootsFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        KeyFn: Fn(&Item) -> Key + Send + Sync + 'static,
+        ChildrenFn: Fn(&Item) -> Vec<Item> + Send + Sync + 'static,
+        EachFn: Fn(&Item, TreeNode, &mut ChildBuilder) + Send + Sync + 'static,
+    > TreeLevel<'_, M, Item, Key, ItemIter, RootsFn, KeyFn, ChildrenFn, EachFn>
+{
+    /// Returns the expanded state for a key, creating it if needed.
+    fn expanded(&mut self, world: &mut World, key: &Key) -> Mutable<bool> {
+        if let Some(expanded) = self.tree.expanded.get(key) {
+            return *expanded;
+        }
+        let owner = *self
+            .tree
+            .owner
+            .get_or_insert_with(|| world.spawn(MutableScope).id());
+        let expanded = world.create_mutable_in(owner, self.tree.default_expanded);
+        self.tree.expanded.insert(key.clone(), expanded);
+        expanded
+    }
+
+    /// Build or raze the children of a node, depending on whether it is expanded.
+    fn update_children(&mut self, world: &mut World, node: &mut TreeItem<Item, Key>) {
+        let expanded = self.expanded(world, &node.key);
+        let prev_children = std::mem::take(&mut node.children);
+        if world.read_mutable(&expanded) {
+            let items = (self.tree.children)(&node.item);
+            let mut level = TreeLevel {
+                tree: &mut *self.tree,
+                depth: self.depth + 1,
+            };
+            node.children = reconcile(&mut level, world, prev_children, &items);
+        } else {
+            for child in prev_children {
+                world.entity_mut(child.child).despawn_recursive();
+            }
+        }
+        let children: Vec<Entity> = node.children.iter().map(|c| c.child).collect();
+        world.entity_mut(node.container).replace_children(&children);
+    }
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + Send + Sync + 'static,
+        Key: Hash + Eq + Clone + Send + Sync + 'static,
+        ItemIter: Iterator<Item = Item> + 'static,
+        RootsFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        KeyFn: Fn(&Item) -> Key + Send + Sync + 'static,
+        ChildrenFn: Fn(&Item) -> Vec<Item> + Send + Sync + 'static,
+        EachFn: Fn(&Item, TreeNode, &mut ChildBuilder) + Send + Sync + 'static,
+    > Reconciler<Item>
+    for TreeLevel<'_, M, Item, Key, ItemIter, RootsFn, KeyFn, ChildrenFn, EachFn>
+{
+    type State = TreeItem<Item, Key>;
+
+    fn matches(&self, state: &TreeItem<Item, Key>, item: &Item) -> bool {
+        state.key == (self.tree.key)(item)
+    }
+
+    fn build(&mut self, world: &mut World, item: &Item) -> TreeItem<Item, Key> {
+        let key = (self.tree.key)(item);
+        let node = TreeNode {
+            depth: self.depth,
+            expanded: self.expanded(world, &key),
+        };
+        let content = world.spawn(GhostNode::default()).id();
+        let container = world.spawn(GhostNode::default()).id();
+        let child = world
+            .spawn(GhostNode::default())
+            .add_children(&[content, container])
+            .id();
+        world.commands().entity(content).with_children(|builder| {
+            (self.tree.each)(item, node, builder);
+        });
+        let mut state = TreeItem {
+            child,
+            container,
+            key,
+            item: item.clone(),
+            children: Vec::new(),
+        };
+        self.update_children(world, &mut state);
+        state
+    }
+
+    fn retain(
+        &mut self,
+        world: &mut World,
+        mut state: TreeItem<Item, Key>,
+        item: &Item,
+    ) -> TreeItem<Item, Key> {
+        state.item = item.clone();
+        self.update_children(world, &mut state);
+        state
+    }
+
+    fn raze(&mut self, world: &mut World, state: TreeItem<Item, Key>) {
+        world.entity_mut(state.child).despawn_recursive();
+    }
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + Send + Sync + 'static,
+        Key: Hash + Eq + Clone + Send + Sync + 'static,
+        ItemIter: Iterator<Item = Item> + 'static,
+        RootsFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        KeyFn: Fn(&Item) -> Key + Send + Sync + 'static,
+        ChildrenFn: Fn(&Item) -> Vec<Item> + Send + Sync + 'static,
+        EachFn: Fn(&Item, TreeNode, &mut ChildBuilder) + Send + Sync + 'static,
+    > AnyEffect for ForTree<M, Item, Key, ItemIter, RootsFn, KeyFn, ChildrenFn, EachFn>
+{
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        // The first time we run, we need to register the one-shot system.
+        if let Some(roots_fn) = self.roots_fn.take() {
+            self.roots_sys = Some(world.register_system(roots_fn));
+        }
+
+        let Some(roots_sys) = self.roots_sys else {
+            return;
+        };
+
+        let roots: Vec<Item> = match world.run_system(roots_sys) {
+            Ok(roots) => roots.collect(),
+            Err(_) => Vec::default(),
+        };
+
+        let prev_state = std::mem::take(&mut self.state);
+        let mut level = TreeLevel {
+            tree: self,
+            depth: 0,
+        };
+        self.state = reconcile(&mut level, world, prev_state, &roots);
+        let children: Vec<Entity> = self.state.iter().map(|c| c.child).collect();
+        world.entity_mut(entity).replace_children(&children);
+
+        // Discard the expanded state of items which are no longer in the tree.
+        let mut keys = HashSet::default();
+        self.collect_keys(&roots, &mut keys);
+        self.expanded.retain(|key, expanded| {
+            let present = keys.contains(key);
+            if !present {
+                if let Ok(cell) = world.get_entity_mut(expanded.id()) {
+                    cell.despawn_recursive();
+                }
+            }
+            present
+        });
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        if let Some(roots_sys) = self.roots_sys {
+            world.commands().queue(UnregisterSystemCommand(roots_sys));
+        }
+        if let Some(owner) = self.owner {
+            world.commands().entity(owner).despawn_recursive();
+        }
+    }
+}
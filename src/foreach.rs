@@ -1,23 +1,72 @@
 use std::{
-    ops::Range,
+    hash::Hash,
     sync::{Arc, Mutex},
 };
 
-use bevy::{ecs::system::SystemId, prelude::*, ui::experimental::GhostNode};
+use bevy::{
+    ecs::{
+        component::Tick,
+        query::{QueryFilter, QueryItem, ReadOnlyQueryData},
+        system::SystemId,
+        world::{CommandQueue, DeferredWorld},
+    },
+    prelude::*,
+    ui::experimental::GhostNode,
+    utils::{HashMap, HashSet},
+};
 
 use crate::{
     effect::{AnyEffect, UnregisterSystemCommand},
-    lcs::lcs,
-    EffectCell,
+    mutable::{ReadMutable, WriteMutable},
+    mutable_vec::VecOp,
+    reconcile::{reconcile, Reconciler},
+    BuildChildrenFn, ChildTuple, CreateMutable, CreateMutableIn, EffectCell, Mutable, MutableScope,
+    MutableVec,
 };
 
 pub struct For {}
 
+/// Entity event triggered on a [`For`] node when an item's ghost node has been inserted. This is
+/// not triggered for the items that are present when the list is first built.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ItemInserted {
+    /// The ghost node containing the item.
+    pub child: Entity,
+    /// The index of the item in the list.
+    pub index: usize,
+}
+
+/// Entity event triggered on a [`For`] node when an item has been removed. The item's ghost node
+/// has already been despawned by the time this is triggered.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ItemRemoved {
+    /// The ghost node which contained the item.
+    pub child: Entity,
+    /// The index the item had in the list before it was removed.
+    pub index: usize,
+}
+
+/// Entity event triggered on a [`For`] node when a retained item has moved relative to the other
+/// retained items. Items whose index only shifted because of insertions or removals elsewhere in
+/// the list are not considered to have moved. This is triggered by [`For::query`] and
+/// [`For::each_vec`], which keep an item's ghost node when it is reordered. Lists which are
+/// reconciled, such as [`For::each`], rebuild a reordered item instead, so the move shows up as an
+/// [`ItemRemoved`] followed by an [`ItemInserted`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ItemMoved {
+    /// The ghost node containing the item.
+    pub child: Entity,
+    /// The previous index of the item.
+    pub from: usize,
+    /// The new index of the item.
+    pub to: usize,
+}
+
 impl For {
     pub fn each<
         M: Send + Sync + 'static,
         Item: Send + Sync + 'static + Clone + PartialEq,
-        ItemIter: 'static + Iterator<Item = Item>,
+        ItemIter: 'static + IntoIterator<Item = Item>,
         ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
         EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
         FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
@@ -30,7 +79,7 @@ impl For {
             items_fn: Some(items_fn),
             item_sys: None,
             cmp: PartialEq::eq,
-            each,
+            each: EachTemplate(each),
             fallback,
             state: Vec::new(),
             marker: std::marker::PhantomData,
@@ -41,7 +90,7 @@ impl For {
         M: Send + Sync + 'static,
         Item: Send + Sync + 'static + Clone,
         CmpFn: Send + Sync + 'static + Fn(&Item, &Item) -> bool,
-        ItemIter: 'static + Iterator<Item = Item>,
+        ItemIter: 'static + IntoIterator<Item = Item>,
         ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
         EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
         FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
@@ -55,21 +104,494 @@ impl For {
             items_fn: Some(items_fn),
             item_sys: None,
             cmp,
-            each,
+            each: EachTemplate(each),
             fallback,
             state: Vec::new(),
             marker: std::marker::PhantomData,
         })))
     }
 
+    /// Like [`For::each`], except that `each` and `fallback` return a [`ChildTuple`] rather than
+    /// building the children imperatively.
+    pub fn each_tuple<
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        ItemIter: 'static + IntoIterator<Item = Item>,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        C: ChildTuple,
+        EachFn: Send + Sync + 'static + Fn(&Item) -> C,
+        F: ChildTuple,
+        FallbackFn: Fn() -> F + Send + Sync + 'static,
+    >(
+        items_fn: ItemFn,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(ForEachEffect {
+            items_fn: Some(items_fn),
+            item_sys: None,
+            cmp: PartialEq::eq,
+            each: TupleTemplate(each),
+            fallback: move |builder: &mut ChildBuilder| builder.children(fallback()),
+            state: Vec::new(),
+            marker: std::marker::PhantomData,
+        })))
+    }
+
+    /// Like [`For::each`], except that each item is also given a [`Mutable`] containing
+    /// per-item state. The state is keyed by the value returned from `key`, rather than being
+    /// owned by the item's ghost node, so it is preserved for as long as the key is present in
+    /// the list, even if the item is moved or rebuilt. `init` creates the state for new keys.
+    pub fn each_with_state<
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        Key: Send + Sync + 'static + Hash + Eq,
+        State: Send + Sync + 'static,
+        ItemIter: 'static + IntoIterator<Item = Item>,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        KeyFn: Send + Sync + 'static + Fn(&Item) -> Key,
+        InitFn: Send + Sync + 'static + Fn(&Item) -> State,
+        EachFn: Send + Sync + 'static + Fn(&Item, Mutable<State>, &mut ChildBuilder),
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    >(
+        items_fn: ItemFn,
+        key: KeyFn,
+        init: InitFn,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(ForEachEffect {
+            items_fn: Some(items_fn),
+            item_sys: None,
+            cmp: PartialEq::eq,
+            each: StateTemplate {
+                key,
+                init,
+                each,
+                states: HashMap::default(),
+                owner: None,
+            },
+            fallback,
+            state: Vec::new(),
+            marker: std::marker::PhantomData,
+        })))
+    }
+
+    /// Like [`For::each_cmp`], except that each item is also given a [`Mutable`] which holds
+    /// the current value of that item. When an item matches an item from the previous update,
+    /// its subtree is retained and the new value is written to the mutable, so effects within
+    /// the subtree which read the mutable will update in place.
+    pub fn each_mutable<
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        CmpFn: Send + Sync + 'static + Fn(&Item, &Item) -> bool,
+        ItemIter: 'static + IntoIterator<Item = Item>,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        EachFn: Send + Sync + 'static + Fn(&Item, Mutable<Item>, &mut ChildBuilder),
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    >(
+        items_fn: ItemFn,
+        cmp: CmpFn,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(ForEachEffect {
+            items_fn: Some(items_fn),
+            item_sys: None,
+            cmp,
+            each: MutableTemplate(each),
+            fallback,
+            state: Vec::new(),
+            marker: std::marker::PhantomData,
+        })))
+    }
+
+    /// Render one child for each entity which matches the query `D` with filter `F`. Entities
+    /// are ordered by [`Entity`] unless a sort key is given via [`ForQuery::sort_by_key`]. An
+    /// entity's row is only rebuilt when one of the components read by `D` changes.
+    pub fn query<D: ReadOnlyQueryData + 'static, F: QueryFilter + 'static>() -> ForQuery<D, F> {
+        ForQuery {
+            sort: Box::new(|rows| rows.sort_by_key(|(entity, _)| *entity)),
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Like [`For::each`], except that items are grouped by the key returned from `group`, and
+    /// each group is preceded by a header. Groups are ordered by the first appearance of their
+    /// key. Both the groups and the items within each group are reconciled, so changing the
+    /// items in one group does not rebuild the others. Grouped lists do not trigger
+    /// [`ItemInserted`], [`ItemRemoved`] or [`ItemMoved`].
+    pub fn grouped<
+        M: Send + Sync + 'static,
+        Item: Send + Sync + 'static + Clone + PartialEq,
+        Key: Send + Sync + 'static + Clone + PartialEq,
+        ItemIter: 'static + IntoIterator<Item = Item>,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        GroupFn: Send + Sync + 'static + Fn(&Item) -> Key,
+        HeaderFn: Send + Sync + 'static + Fn(&Key, &mut ChildBuilder),
+        EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    >(
+        items_fn: ItemFn,
+        group: GroupFn,
+        header: HeaderFn,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(ForGroupedEffect {
+            items_fn: Some(items_fn),
+            item_sys: None,
+            group,
+            header,
+            each: EachTemplate(each),
+            fallback,
+            state: Vec::new(),
+            marker: std::marker::PhantomData,
+        })))
+    }
+
+    /// Render one child for each item in a [`MutableVec`]. Rather than diffing the list, the
+    /// operations recorded by the vector are applied directly to the child entities.
+    pub fn each_vec<
+        Item: Send + Sync + 'static,
+        EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    >(
+        items: MutableVec<Item>,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(ForVecEffect {
+            items,
+            each,
+            fallback,
+            seq: None,
+            children: Vec::new(),
+        })))
+    }
+
     // pub fn index<F: FnMut()>(self, f: F) {}
 
     // pub fn index_cmp<F: FnMut()>(self, f: F) {}
 }
 
+/// Sorts the matching entities of a [`ForQuery`].
+type QuerySortFn<D> = Box<dyn for<'w> Fn(&mut [(Entity, QueryItem<'w, D>)]) + Send + Sync>;
+
+/// Builder for a [`For`] node over the entities that match a query.
+pub struct ForQuery<D: ReadOnlyQueryData, F: QueryFilter> {
+    sort: QuerySortFn<D>,
+    marker: std::marker::PhantomData<F>,
+}
+
+impl<D: ReadOnlyQueryData + 'static, F: QueryFilter + 'static> ForQuery<D, F> {
+    /// Order the matching entities by a sort key. Entities with equal keys are ordered by
+    /// [`Entity`].
+    pub fn sort_by_key<K: Ord, KeyFn: Fn(&QueryItem<D>) -> K + Send + Sync + 'static>(
+        mut self,
+        key: KeyFn,
+    ) -> Self {
+        self.sort = Box::new(move |rows| {
+            rows.sort_by_cached_key(|(entity, item)| (key(item), *entity));
+        });
+        self
+    }
+
+    /// Build the list node. `each` is called with the entity for each row.
+    pub fn each<
+        EachFn: Send + Sync + 'static + Fn(Entity, &mut ChildBuilder),
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    >(
+        self,
+        each: EachFn,
+        fallback: FallbackFn,
+    ) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(ForQueryEffect::<D, F, _, _> {
+            query: None,
+            sort: self.sort,
+            each,
+            fallback,
+            rows: HashMap::default(),
+            order: Vec::new(),
+            last_run: Tick::new(0),
+        })))
+    }
+}
+
+/// A reaction that renders one child for each entity which matches a query. Rows are keyed by
+/// entity, so the list is updated incrementally rather than diffed: nothing is sorted or rebuilt
+/// unless an entity starts or stops matching, or one of its queried components changes.
+struct ForQueryEffect<D: ReadOnlyQueryData, F: QueryFilter, EachFn, FallbackFn> {
+    query: Option<QueryState<(Entity, D), F>>,
+    sort: QuerySortFn<D>,
+    each: EachFn,
+    fallback: FallbackFn,
+    /// The ghost node for each matching entity.
+    rows: HashMap<Entity, Entity>,
+    /// The matching entities, in display order.
+    order: Vec<Entity>,
+    last_run: Tick,
+}
+
+impl<
+        D: ReadOnlyQueryData + 'static,
+        F: QueryFilter + 'static,
+        EachFn: Fn(Entity, &mut ChildBuilder) + Send + Sync + 'static,
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    > ForQueryEffect<D, F, EachFn, FallbackFn>
+{
+    /// Spawn a ghost node for a new row and build its children.
+    fn build_row(&self, world: &mut World, entity: Entity) -> Entity {
+        let child = world.spawn(GhostNode::default()).id();
+        world.commands().entity(child).with_children(|builder| {
+            (self.each)(entity, builder);
+        });
+        child
+    }
+}
+
+impl<
+        D: ReadOnlyQueryData + 'static,
+        F: QueryFilter + 'static,
+        EachFn: Fn(Entity, &mut ChildBuilder) + Send + Sync + 'static,
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    > AnyEffect for ForQueryEffect<D, F, EachFn, FallbackFn>
+{
+    fn update(&mut self, world: &mut World, parent: Entity) {
+        let first = self.query.is_none();
+        let query = self
+            .query
+            .get_or_insert_with(|| world.query_filtered::<(Entity, D), F>());
+        query.update_archetypes(world);
+        let query = &*query;
+        // Advance the change tick, as running a system would, so that changes made after this
+        // point are seen by the next update.
+        let this_run = world.increment_change_tick();
+
+        // Look for entities which started matching, and rows whose queried components changed,
+        // by checking change ticks in the same way as the `Added` and `Changed` filters.
+        let access = query.component_access().access();
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        let mut matched = 0;
+        for (entity, _) in query.iter_manual(world) {
+            matched += 1;
+            if !self.rows.contains_key(&entity) {
+                added.push(entity);
+                continue;
+            }
+            let entt = world.entity(entity);
+            if entt.archetype().components().any(|id| {
+                access.has_component_read(id)
+                    && entt
+                        .get_change_ticks_by_id(id)
+                        .is_some_and(|ticks| ticks.is_changed(self.last_run, this_run))
+            }) {
+                changed.push(entity);
+            }
+        }
+        self.last_run = this_run;
+
+        // Entities which stopped matching, either because they were despawned or because their
+        // components were removed. The matched count can only differ if there are some.
+        let mut removed = Vec::new();
+        if matched != self.rows.len() + added.len() {
+            removed.extend(
+                self.order
+                    .iter()
+                    .copied()
+                    .filter(|entity| query.get_manual(world, *entity).is_err()),
+            );
+        }
+
+        if !first && added.is_empty() && removed.is_empty() && changed.is_empty() {
+            return;
+        }
+
+        // Rebuild changed rows in place.
+        for entity in changed {
+            let child = self.rows[&entity];
+            world.entity_mut(child).despawn_descendants();
+            world.commands().entity(child).with_children(|builder| {
+                (self.each)(entity, builder);
+            });
+        }
+
+        let prev_children: Vec<Entity> = self.order.iter().map(|e| self.rows[e]).collect();
+        for entity in removed {
+            if let Some(child) = self.rows.remove(&entity) {
+                world.entity_mut(child).despawn_recursive();
+            }
+        }
+        for entity in added {
+            let child = self.build_row(world, entity);
+            self.rows.insert(entity, child);
+        }
+
+        // Sort keys can only have changed if membership or a row's components changed.
+        let query = self.query.as_ref().unwrap();
+        let mut sorted = query
+            .iter_many_manual(world, self.rows.keys())
+            .collect::<Vec<_>>();
+        (self.sort)(&mut sorted);
+        let order: Vec<Entity> = sorted.into_iter().map(|(entity, _)| entity).collect();
+        let children: Vec<Entity> = order.iter().map(|e| self.rows[e]).collect();
+        let prev_len = self.order.len();
+        self.order = order;
+
+        if children.is_empty() {
+            if prev_len > 0 || first {
+                // Transitioning from non-empty to empty, generate fallback.
+                world.entity_mut(parent).despawn_descendants();
+                world.commands().entity(parent).with_children(|builder| {
+                    (self.fallback)(builder);
+                });
+            }
+        } else {
+            if prev_len == 0 {
+                // Transitioning from empty to non-empty, delete fallback.
+                world.entity_mut(parent).despawn_descendants();
+            }
+            if children != prev_children {
+                world.entity_mut(parent).replace_children(&children);
+            }
+        }
+
+        if !first {
+            trigger_item_events(world, parent, &prev_children, &children);
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
+
+/// Builds the children of an individual list item, and refreshes them when the item is retained.
+trait ItemTemplate<Item>: Send + Sync + 'static {
+    /// Build the children of the item's ghost node. Returns the per-item mutable cell, if any.
+    fn build(&self, item: &Item, builder: &mut ChildBuilder) -> Option<Entity>;
+
+    /// Write the updated value of a retained item to its mutable cell.
+    fn refresh(&self, world: &mut World, cell: Entity, item: &Item);
+
+    /// Called with the full list of items before the list is reconciled.
+    fn prepare(&mut self, _world: &mut World, _items: &[Item]) {}
+
+    /// Called when the list is despawned.
+    fn cleanup(&self, _world: &mut DeferredWorld) {}
+}
+
+/// Item template which builds the item once and never updates it.
+struct EachTemplate<EachFn>(EachFn);
+
+impl<Item, EachFn: Fn(&Item, &mut ChildBuilder) + Send + Sync + 'static> ItemTemplate<Item>
+    for EachTemplate<EachFn>
+{
+    fn build(&self, item: &Item, builder: &mut ChildBuilder) -> Option<Entity> {
+        (self.0)(item, builder);
+        None
+    }
+
+    fn refresh(&self, _world: &mut World, _cell: Entity, _item: &Item) {}
+}
+
+/// Item template which builds the item from a [`ChildTuple`].
+struct TupleTemplate<EachFn>(EachFn);
+
+impl<Item, C: ChildTuple, EachFn: Fn(&Item) -> C + Send + Sync + 'static> ItemTemplate<Item>
+    for TupleTemplate<EachFn>
+{
+    fn build(&self, item: &Item, builder: &mut ChildBuilder) -> Option<Entity> {
+        builder.children((self.0)(item));
+        None
+    }
+
+    fn refresh(&self, _world: &mut World, _cell: Entity, _item: &Item) {}
+}
+
+/// Item template which gives each item a [`Mutable`] holding state that is keyed by the item's
+/// identity, rather than owned by the item's ghost node.
+struct StateTemplate<Key, State, KeyFn, InitFn, EachFn> {
+    key: KeyFn,
+    init: InitFn,
+    each: EachFn,
+    states: HashMap<Key, Mutable<State>>,
+    /// Entity which owns the state cells. This can't be the list node itself, since its children
+    /// are replaced on every update.
+    owner: Option<Entity>,
+}
+
+impl<
+        Item,
+        Key: Hash + Eq + Send + Sync + 'static,
+        State: Send + Sync + 'static,
+        KeyFn: Fn(&Item) -> Key + Send + Sync + 'static,
+        InitFn: Fn(&Item) -> State + Send + Sync + 'static,
+        EachFn: Fn(&Item, Mutable<State>, &mut ChildBuilder) + Send + Sync + 'static,
+    > ItemTemplate<Item> for StateTemplate<Key, State, KeyFn, InitFn, EachFn>
+{
+    fn build(&self, item: &Item, builder: &mut ChildBuilder) -> Option<Entity> {
+        let state = self.states[&(self.key)(item)];
+        (self.each)(item, state, builder);
+        None
+    }
+
+    fn refresh(&self, _world: &mut World, _cell: Entity, _item: &Item) {}
+
+    fn prepare(&mut self, world: &mut World, items: &[Item]) {
+        // Create state for new keys, and discard the state of keys which are no longer present.
+        let mut states = HashMap::with_capacity(items.len());
+        let owner = *self
+            .owner
+            .get_or_insert_with(|| world.spawn(MutableScope).id());
+        for item in items {
+            let key = (self.key)(item);
+            if states.contains_key(&key) {
+                continue;
+            }
+            let state = match self.states.remove(&key) {
+                Some(state) => state,
+                None => world.create_mutable_in(owner, (self.init)(item)),
+            };
+            states.insert(key, state);
+        }
+        for state in self.states.values() {
+            if let Ok(cell) = world.get_entity_mut(state.id()) {
+                cell.despawn_recursive();
+            }
+        }
+        self.states = states;
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld) {
+        if let Some(owner) = self.owner {
+            world.commands().entity(owner).despawn_recursive();
+        }
+    }
+}
+
+/// Item template which stores the item in a [`Mutable`] that is updated in place.
+struct MutableTemplate<EachFn>(EachFn);
+
+impl<
+        Item: Clone + PartialEq + Send + Sync + 'static,
+        EachFn: Fn(&Item, Mutable<Item>, &mut ChildBuilder) + Send + Sync + 'static,
+    > ItemTemplate<Item> for MutableTemplate<EachFn>
+{
+    fn build(&self, item: &Item, builder: &mut ChildBuilder) -> Option<Entity> {
+        let mutable = builder.create_mutable(item.clone());
+        (self.0)(item, mutable, builder);
+        Some(mutable.id())
+    }
+
+    fn refresh(&self, world: &mut World, cell: Entity, item: &Item) {
+        world.write_mutable(cell, item.clone());
+    }
+}
+
 #[derive(Clone)]
 struct ListItem<Item: Clone> {
     child: Entity,
+    cell: Option<Entity>,
     item: Item,
 }
 
@@ -78,9 +600,9 @@ struct ForEachEffect<
     M,
     Item: Clone,
     CmpFn: Fn(&Item, &Item) -> bool,
-    ItemIter: Iterator<Item = Item>,
+    ItemIter: IntoIterator<Item = Item>,
     ItemFn: IntoSystem<(), ItemIter, M>,
-    EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
+    Each: ItemTemplate<Item>,
     FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
 > where
     Self: Send + Sync,
@@ -88,162 +610,70 @@ struct ForEachEffect<
     items_fn: Option<ItemFn>,
     item_sys: Option<SystemId<(), ItemIter>>,
     cmp: CmpFn,
-    each: EachFn,
+    each: Each,
     fallback: FallbackFn,
     state: Vec<ListItem<Item>>,
     marker: std::marker::PhantomData<M>,
 }
 
+/// Reconciles a list of items, each of which is rendered within its own ghost node.
+struct ListReconciler<'a, CmpFn, Each> {
+    cmp: &'a CmpFn,
+    each: &'a Each,
+}
+
 impl<
-        M: Send + Sync + 'static,
         Item: Clone + Send + Sync + 'static,
         CmpFn: Fn(&Item, &Item) -> bool + Send + Sync + 'static,
-        ItemIter: Iterator<Item = Item>,
-        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
-        EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
-        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
-    > ForEachEffect<M, Item, CmpFn, ItemIter, ItemFn, EachFn, FallbackFn>
+        Each: ItemTemplate<Item>,
+    > Reconciler<Item> for ListReconciler<'_, CmpFn, Each>
 {
-    /// Uses the sequence of key values to match the previous array items with the updated
-    /// array items. Matching items are patched, other items are inserted or deleted.
-    ///
-    /// # Arguments
-    ///
-    /// * `bc` - [`BuildContext`] used to build individual elements.
-    /// * `prev_state` - Array of view state elements from previous update.
-    /// * `prev_range` - The range of elements we are comparing in `prev_state`.
-    /// * `next_state` - Array of view state elements to be built.
-    /// * `next_range` - The range of elements we are comparing in `next_state`.
-    #[allow(clippy::too_many_arguments, clippy::needless_range_loop)]
-    fn build_recursive(
-        &self,
-        world: &mut World,
-        prev_state: &[ListItem<Item>],
-        prev_range: Range<usize>,
-        next_items: &[Item],
-        next_range: Range<usize>,
-        out: &mut Vec<ListItem<Item>>,
-    ) {
-        // Look for longest common subsequence.
-        // prev_start and next_start are *relative to the slice*.
-        let (prev_start, next_start, lcs_length) = lcs(
-            &prev_state[prev_range.clone()],
-            &next_items[next_range.clone()],
-            |a, b| (self.cmp)(&a.item, b),
-        );
+    type State = ListItem<Item>;
 
-        // If there was nothing in common
-        if lcs_length == 0 {
-            // Raze old elements
-            for i in prev_range {
-                let prev = &prev_state[i];
-                world.entity_mut(prev.child).despawn_recursive();
-            }
-            // Build new elements
-            for i in next_range {
-                let child_id = world.spawn(GhostNode::default()).id();
-                world.commands().entity(child_id).with_children(|builder| {
-                    (self.each)(&next_items[i], builder);
-                });
-                out.push(ListItem {
-                    child: child_id,
-                    item: next_items[i].clone(),
-                });
-            }
-            return;
-        }
+    fn matches(&self, state: &ListItem<Item>, item: &Item) -> bool {
+        (self.cmp)(&state.item, item)
+    }
 
-        // Adjust prev_start and next_start to be relative to the entire state array.
-        let prev_start = prev_start + prev_range.start;
-        let next_start = next_start + next_range.start;
-
-        // Stuff that precedes the LCS.
-        if prev_start > prev_range.start {
-            if next_start > next_range.start {
-                // Both prev and next have entries before lcs, so recurse
-                self.build_recursive(
-                    world,
-                    // owner,
-                    prev_state,
-                    prev_range.start..prev_start,
-                    next_items,
-                    next_range.start..next_start,
-                    out,
-                )
-            } else {
-                // Deletions
-                for i in prev_range.start..prev_start {
-                    let prev = &prev_state[i];
-                    world.entity_mut(prev.child).despawn_recursive();
-                }
-            }
-        } else if next_start > next_range.start {
-            // Insertions
-            for i in next_range.start..next_start {
-                let child_id = world.spawn(GhostNode::default()).id();
-                world.commands().entity(child_id).with_children(|builder| {
-                    (self.each)(&next_items[i], builder);
-                });
-                out.push(ListItem {
-                    child: child_id,
-                    item: next_items[i].clone(),
-                });
-            }
+    /// Spawn a ghost node for a new item and build its children.
+    fn build(&mut self, world: &mut World, item: &Item) -> ListItem<Item> {
+        let child = world.spawn(GhostNode::default()).id();
+        let mut cell = None;
+        world.commands().entity(child).with_children(|builder| {
+            cell = self.each.build(item, builder);
+        });
+        ListItem {
+            child,
+            cell,
+            item: item.clone(),
         }
+    }
 
-        // For items that match, copy over the view and value.
-        for i in 0..lcs_length {
-            let prev = &prev_state[prev_start + i];
-            out.push(prev.clone());
+    /// Keep the view but refresh the value.
+    fn retain(&mut self, world: &mut World, state: ListItem<Item>, item: &Item) -> ListItem<Item> {
+        if let Some(cell) = state.cell {
+            self.each.refresh(world, cell, item);
         }
-
-        // Stuff that follows the LCS.
-        let prev_end = prev_start + lcs_length;
-        let next_end = next_start + lcs_length;
-        if prev_end < prev_range.end {
-            if next_end < next_range.end {
-                // Both prev and next have entries after lcs, so recurse
-                self.build_recursive(
-                    world,
-                    // owner,
-                    prev_state,
-                    prev_end..prev_range.end,
-                    next_items,
-                    next_end..next_range.end,
-                    out,
-                );
-            } else {
-                // Deletions
-                for i in prev_end..prev_range.end {
-                    let prev = &prev_state[i];
-                    world.entity_mut(prev.child).despawn_recursive();
-                }
-            }
-        } else if next_end < next_range.end {
-            // Insertions
-            for i in next_end..next_range.end {
-                let child_id = world.spawn(GhostNode::default()).id();
-                world.commands().entity(child_id).with_children(|builder| {
-                    (self.each)(&next_items[i], builder);
-                });
-                out.push(ListItem {
-                    child: child_id,
-                    item: next_items[i].clone(),
-                });
-            }
+        ListItem {
+            child: state.child,
+            cell: state.cell,
+            item: item.clone(),
         }
     }
+
+    fn raze(&mut self, world: &mut World, state: ListItem<Item>) {
+        world.entity_mut(state.child).despawn_recursive();
+    }
 }
 
 impl<
         M: Send + Sync + 'static,
         Item: Clone + Send + Sync + 'static,
         CmpFn: Fn(&Item, &Item) -> bool + Send + Sync + 'static,
-        ItemIter: Iterator<Item = Item> + 'static,
+        ItemIter: IntoIterator<Item = Item> + 'static,
         ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
-        EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
+        Each: ItemTemplate<Item>,
         FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
-    > AnyEffect for ForEachEffect<M, Item, CmpFn, ItemIter, ItemFn, EachFn, FallbackFn>
+    > AnyEffect for ForEachEffect<M, Item, CmpFn, ItemIter, ItemFn, Each, FallbackFn>
 {
     fn update(&mut self, world: &mut World, parent: Entity) {
         let mut first = false;
@@ -258,23 +688,21 @@ impl<
 
         // Create a reactive context and call the test condition.
         let items: Vec<Item> = match world.run_system(items_sys) {
-            Ok(items) => items.collect(),
+            Ok(items) => items.into_iter().collect(),
             Err(_) => Vec::default(),
         };
-        let mut next_state: Vec<ListItem<Item>> = Vec::with_capacity(items.len());
         let next_len = items.len();
         let prev_len = self.state.len();
 
-        self.build_recursive(
-            world,
-            &self.state,
-            0..prev_len,
-            &items,
-            0..next_len,
-            &mut next_state,
-        );
-        let children: Vec<Entity> = next_state.iter().map(|i| i.child).collect();
-        self.state = std::mem::take(&mut next_state);
+        self.each.prepare(world, &items);
+        let prev_state = std::mem::take(&mut self.state);
+        let prev_children: Vec<Entity> = prev_state.iter().map(|i| i.child).collect();
+        let mut reconciler = ListReconciler {
+            cmp: &self.cmp,
+            each: &self.each,
+        };
+        self.state = reconcile(&mut reconciler, world, prev_state, &items);
+        let children: Vec<Entity> = self.state.iter().map(|i| i.child).collect();
 
         if next_len == 0 {
             if prev_len > 0 || first {
@@ -291,11 +719,669 @@ impl<
             }
             world.entity_mut(parent).replace_children(&children);
         }
+
+        if !first {
+            trigger_item_events(world, parent, &prev_children, &children);
+        }
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        if let Some(items_sys) = self.item_sys {
+            world.commands().queue(UnregisterSystemCommand(items_sys));
+        }
+        self.each.cleanup(world);
+    }
+}
+
+/// Compare the children of a list before and after an update, and trigger the corresponding
+/// lifecycle events on the list entity.
+fn trigger_item_events(world: &mut World, parent: Entity, prev: &[Entity], next: &[Entity]) {
+    let prev_index: HashMap<Entity, usize> =
+        prev.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+    let next_index: HashMap<Entity, usize> =
+        next.iter().enumerate().map(|(i, e)| (*e, i)).collect();
+    // The retained children which stayed in order relative to each other are the longest
+    // increasing run of previous indices, taken in the new order. Everything else has moved.
+    let retained: Vec<(Entity, usize)> = next
+        .iter()
+        .filter_map(|child| prev_index.get(child).map(|from| (*child, *from)))
+        .collect();
+    let in_order = longest_increasing(&retained);
+    for (index, child) in prev.iter().enumerate() {
+        if !next_index.contains_key(child) {
+            world.trigger_targets(
+                ItemRemoved {
+                    child: *child,
+                    index,
+                },
+                parent,
+            );
+        }
+    }
+    for (index, child) in next.iter().enumerate() {
+        match prev_index.get(child) {
+            None => world.trigger_targets(
+                ItemInserted {
+                    child: *child,
+                    index,
+                },
+                parent,
+            ),
+            Some(from) if !in_order.contains(child) => world.trigger_targets(
+                ItemMoved {
+                    child: *child,
+                    from: *from,
+                    to: index,
+                },
+                parent,
+            ),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Returns the entities of the longest subsequence of `items` whose indices are increasing.
+fn longest_increasing(items: &[(Entity, usize)]) -> HashSet<Entity> {
+    // `tails[k]` is the position in `items` of the smallest index ending a run of length k + 1.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; items.len()];
+    for (pos, (_, index)) in items.iter().enumerate() {
+        let k = tails.partition_point(|&t| items[t].1 < *index);
+        prev[pos] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(pos);
+        } else {
+            tails[k] = pos;
+        }
+    }
+    let mut result = HashSet::default();
+    let mut pos = tails.last().copied();
+    while let Some(p) = pos {
+        result.insert(items[p].0);
+        pos = prev[p];
+    }
+    result
+}
+
+/// View state for a group of items.
+struct ListGroup<Key, Item: Clone> {
+    /// Ghost node containing the header and the items.
+    child: Entity,
+    header: Entity,
+    key: Key,
+    items: Vec<ListItem<Item>>,
+}
+
+/// A reaction that renders a list of items divided into groups.
+struct ForGroupedEffect<
+    M,
+    Item: Clone,
+    Key,
+    ItemIter: IntoIterator<Item = Item>,
+    ItemFn: IntoSystem<(), ItemIter, M>,
+    GroupFn,
+    HeaderFn,
+    Each,
+    FallbackFn,
+> {
+    items_fn: Option<ItemFn>,
+    item_sys: Option<SystemId<(), ItemIter>>,
+    group: GroupFn,
+    header: HeaderFn,
+    each: Each,
+    fallback: FallbackFn,
+    state: Vec<ListGroup<Key, Item>>,
+    marker: std::marker::PhantomData<M>,
+}
+
+/// Reconciles the list of groups, and the items within each group.
+struct GroupReconciler<'a, HeaderFn, Each> {
+    header: &'a HeaderFn,
+    each: &'a Each,
+}
+
+impl<HeaderFn, Each> GroupReconciler<'_, HeaderFn, Each> {
+    /// Reconcile the items within a group.
+    fn update_items<Key, Item: Clone + PartialEq + Send + Sync + 'static>(
+        &self,
+        world: &mut World,
+        group: &mut ListGroup<Key, Item>,
+        items: &[Item],
+    ) where
+        Each: ItemTemplate<Item>,
+    {
+        let mut reconciler = ListReconciler {
+            cmp: &PartialEq::eq,
+            each: self.each,
+        };
+        let prev_items = std::mem::take(&mut group.items);
+        group.items = reconcile(&mut reconciler, world, prev_items, items);
+        let mut children = Vec::with_capacity(group.items.len() + 1);
+        children.push(group.header);
+        children.extend(group.items.iter().map(|i| i.child));
+        world.entity_mut(group.child).replace_children(&children);
+    }
+}
+
+impl<
+        Key: Clone + PartialEq + Send + Sync + 'static,
+        Item: Clone + PartialEq + Send + Sync + 'static,
+        HeaderFn: Fn(&Key, &mut ChildBuilder) + Send + Sync + 'static,
+        Each: ItemTemplate<Item>,
+    > Reconciler<(Key, Vec<Item>)> for GroupReconciler<'_, HeaderFn, Each>
+{
+    type State = ListGroup<Key, Item>;
+
+    fn matches(&self, state: &ListGroup<Key, Item>, (key, _): &(Key, Vec<Item>)) -> bool {
+        state.key == *key
+    }
+
+    fn build(
+        &mut self,
+        world: &mut World,
+        (key, items): &(Key, Vec<Item>),
+    ) -> ListGroup<Key, Item> {
+        let header = world.spawn(GhostNode::default()).id();
+        world.commands().entity(header).with_children(|builder| {
+            (self.header)(key, builder);
+        });
+        let mut group = ListGroup {
+            child: world.spawn(GhostNode::default()).id(),
+            header,
+            key: key.clone(),
+            items: Vec::new(),
+        };
+        self.update_items(world, &mut group, items);
+        group
+    }
+
+    fn retain(
+        &mut self,
+        world: &mut World,
+        mut state: ListGroup<Key, Item>,
+        (_, items): &(Key, Vec<Item>),
+    ) -> ListGroup<Key, Item> {
+        self.update_items(world, &mut state, items);
+        state
+    }
+
+    fn raze(&mut self, world: &mut World, state: ListGroup<Key, Item>) {
+        world.entity_mut(state.child).despawn_recursive();
+    }
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + PartialEq + Send + Sync + 'static,
+        Key: Clone + PartialEq + Send + Sync + 'static,
+        ItemIter: IntoIterator<Item = Item> + 'static,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        GroupFn: Fn(&Item) -> Key + Send + Sync + 'static,
+        HeaderFn: Fn(&Key, &mut ChildBuilder) + Send + Sync + 'static,
+        Each: ItemTemplate<Item>,
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    > AnyEffect
+    for ForGroupedEffect<M, Item, Key, ItemIter, ItemFn, GroupFn, HeaderFn, Each, FallbackFn>
+{
+    fn update(&mut self, world: &mut World, parent: Entity) {
+        let mut first = false;
+        if let Some(items_fn) = self.items_fn.take() {
+            self.item_sys = Some(world.register_system(items_fn));
+            first = true;
+        }
+
+        let Some(items_sys) = self.item_sys else {
+            return;
+        };
+
+        // Divide the items into groups, in order of the first appearance of each key.
+        let mut groups: Vec<(Key, Vec<Item>)> = Vec::new();
+        if let Ok(items) = world.run_system(items_sys) {
+            for item in items {
+                let key = (self.group)(&item);
+                match groups.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, group)) => group.push(item),
+                    None => groups.push((key, vec![item])),
+                }
+            }
+        }
+        let next_len = groups.len();
+        let prev_len = self.state.len();
+
+        let prev_state = std::mem::take(&mut self.state);
+        let mut reconciler = GroupReconciler {
+            header: &self.header,
+            each: &self.each,
+        };
+        self.state = reconcile(&mut reconciler, world, prev_state, &groups);
+        let children: Vec<Entity> = self.state.iter().map(|g| g.child).collect();
+
+        if next_len == 0 {
+            if prev_len > 0 || first {
+                // Transitioning from non-empty to empty, generate fallback.
+                world.entity_mut(parent).despawn_descendants();
+                world.commands().entity(parent).with_children(|builder| {
+                    (self.fallback)(builder);
+                });
+            }
+        } else {
+            if prev_len == 0 {
+                // Transitioning from empty to non-empty, delete fallback.
+                world.entity_mut(parent).despawn_descendants();
+            }
+            world.entity_mut(parent).replace_children(&children);
+        }
     }
 
-    fn cleanup(&self, world: &mut bevy::ecs::world::DeferredWorld, _entity: Entity) {
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
         if let Some(items_sys) = self.item_sys {
             world.commands().queue(UnregisterSystemCommand(items_sys));
         }
     }
 }
+
+/// A reaction that applies the operations recorded by a [`MutableVec`] to the list children.
+struct ForVecEffect<Item, EachFn, FallbackFn> {
+    items: MutableVec<Item>,
+    each: EachFn,
+    fallback: FallbackFn,
+    seq: Option<usize>,
+    children: Vec<Entity>,
+}
+
+impl<
+        Item: Send + Sync + 'static,
+        EachFn: Send + Sync + 'static + Fn(&Item, &mut ChildBuilder),
+        FallbackFn: Fn(&mut ChildBuilder) + Send + Sync + 'static,
+    > AnyEffect for ForVecEffect<Item, EachFn, FallbackFn>
+{
+    fn update(&mut self, world: &mut World, parent: Entity) {
+        let first = self.seq.is_none();
+        let (seq, ops) = world.read_mutable_map(&self.items.inner, |state| {
+            let ops = self
+                .seq
+                .and_then(|seq| state.ops_since(seq).map(<[_]>::to_vec));
+            (state.seq(), ops)
+        });
+        if self.seq == Some(seq) {
+            return;
+        }
+        self.seq = Some(seq);
+
+        // Apply the operations to the existing children. Slots for new items are left empty.
+        let prev_children = self.children.clone();
+        let prev_len = prev_children.len();
+        let mut slots: Vec<Option<Entity>> = self.children.iter().copied().map(Some).collect();
+        match ops {
+            Some(ops) => {
+                for op in ops {
+                    match op {
+                        VecOp::Insert(index) => slots.insert(index, None),
+                        VecOp::Remove(index) => {
+                            if let Some(child) = slots.remove(index) {
+                                world.entity_mut(child).despawn_recursive();
+                            }
+                        }
+                        VecOp::Replace(index) => {
+                            if let Some(child) = slots[index].take() {
+                                world.entity_mut(child).despawn_recursive();
+                            }
+                        }
+                        VecOp::Swap(a, b) => slots.swap(a, b),
+                        VecOp::Move(from, to) => {
+                            let slot = slots.remove(from);
+                            slots.insert(to, slot);
+                        }
+                        VecOp::Clear => {
+                            for child in slots.drain(..).flatten() {
+                                world.entity_mut(child).despawn_recursive();
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                // The log has been trimmed past our last update, so rebuild everything.
+                for child in slots.drain(..).flatten() {
+                    world.entity_mut(child).despawn_recursive();
+                }
+                slots.resize(self.items.len(world), None);
+            }
+        }
+
+        // Build the new items.
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, world);
+        let items = world.read_mutable_as_ref(&self.items.inner);
+        self.children = slots
+            .into_iter()
+            .zip(items.items.iter())
+            .map(|(slot, item)| {
+                slot.unwrap_or_else(|| {
+                    commands
+                        .spawn(GhostNode::default())
+                        .with_children(|builder| (self.each)(item, builder))
+                        .id()
+                })
+            })
+            .collect();
+        queue.apply(world);
+
+        let next_len = self.children.len();
+        if next_len == 0 {
+            if prev_len > 0 || first {
+                // Transitioning from non-empty to empty, generate fallback.
+                world.entity_mut(parent).despawn_descendants();
+                world.commands().entity(parent).with_children(|builder| {
+                    (self.fallback)(builder);
+                });
+            }
+        } else {
+            if prev_len == 0 {
+                // Transitioning from empty to non-empty, delete fallback.
+                world.entity_mut(parent).despawn_descendants();
+            }
+            world.entity_mut(parent).replace_children(&self.children);
+        }
+
+        if !first {
+            trigger_item_events(world, parent, &prev_children, &self.children);
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{longest_increasing, For, ItemMoved};
+    use crate::{effect::update_effects, CreateMutable, Mutable, MutableVec};
+
+    fn moved(prev_indices: &[usize]) -> Vec<usize> {
+        let items: Vec<(Entity, usize)> = prev_indices
+            .iter()
+            .map(|i| (Entity::from_raw(*i as u32), *i))
+            .collect();
+        let in_order = longest_increasing(&items);
+        let mut moved: Vec<usize> = items
+            .iter()
+            .filter(|(e, _)| !in_order.contains(e))
+            .map(|(_, i)| *i)
+            .collect();
+        moved.sort();
+        moved
+    }
+
+    #[test]
+    fn test_no_moves() {
+        assert!(moved(&[]).is_empty());
+        assert!(moved(&[0, 1, 2]).is_empty());
+        // Gaps left by removed items are not moves.
+        assert!(moved(&[0, 2, 5]).is_empty());
+    }
+
+    #[test]
+    fn test_move_one() {
+        assert_eq!(moved(&[1, 2, 0]), vec![0]);
+        assert_eq!(moved(&[2, 0, 1]), vec![2]);
+        assert_eq!(moved(&[0, 3, 1, 2]), vec![3]);
+    }
+
+    #[test]
+    fn test_reverse() {
+        assert_eq!(moved(&[3, 2, 1, 0]).len(), 3);
+    }
+
+    #[derive(Component)]
+    struct Score(u32);
+
+    #[derive(Component)]
+    struct Other;
+
+    #[derive(Resource, Default)]
+    struct Moves(Vec<(usize, usize)>);
+
+    /// Spawn a list of the entities with a `Score`, ordered by score. Each row holds a single
+    /// child, so a rebuilt row can be detected by its child changing.
+    fn spawn_list(world: &mut World) -> Entity {
+        world.init_resource::<Moves>();
+        world
+            .spawn(
+                For::query::<&Score, ()>()
+                    .sort_by_key(|score| score.0)
+                    .each(
+                        |_, builder| {
+                            builder.spawn_empty();
+                        },
+                        |_| {},
+                    ),
+            )
+            .observe(|trigger: Trigger<ItemMoved>, mut moves: ResMut<Moves>| {
+                moves.0.push((trigger.from, trigger.to));
+            })
+            .id()
+    }
+
+    /// Run the effects, and apply the commands they queued to build rows.
+    fn update(world: &mut World) {
+        update_effects(world);
+        world.flush();
+    }
+
+    /// The row ghost nodes of a list, and the child of each row.
+    fn rows(world: &World, list: Entity) -> Vec<(Entity, Entity)> {
+        world
+            .get::<Children>(list)
+            .map(|rows| {
+                rows.iter()
+                    .map(|row| (*row, world.get::<Children>(*row).unwrap()[0]))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_query_added() {
+        let mut world = World::new();
+        world.spawn(Score(1));
+        let list = spawn_list(&mut world);
+        update(&mut world);
+        let before = rows(&world, list);
+        assert_eq!(before.len(), 1);
+
+        world.spawn(Score(2));
+        update(&mut world);
+        let after = rows(&world, list);
+        assert_eq!(after.len(), 2);
+        assert_eq!(after[0], before[0]);
+    }
+
+    #[test]
+    fn test_query_removed() {
+        let mut world = World::new();
+        let a = world.spawn(Score(1)).id();
+        world.spawn(Score(2));
+        let list = spawn_list(&mut world);
+        update(&mut world);
+        let before = rows(&world, list);
+
+        world.despawn(a);
+        update(&mut world);
+        let after = rows(&world, list);
+        assert_eq!(after, vec![before[1]]);
+        assert!(world.get_entity(before[0].0).is_err());
+    }
+
+    #[test]
+    fn test_query_changed_rebuilds_own_row() {
+        let mut world = World::new();
+        let a = world.spawn(Score(1)).id();
+        world.spawn(Score(5));
+        let list = spawn_list(&mut world);
+        update(&mut world);
+        let before = rows(&world, list);
+
+        world.get_mut::<Score>(a).unwrap().0 = 2;
+        update(&mut world);
+        let after = rows(&world, list);
+        assert_eq!(after[0].0, before[0].0);
+        assert_ne!(after[0].1, before[0].1);
+        assert_eq!(after[1], before[1]);
+        assert!(world.resource::<Moves>().0.is_empty());
+    }
+
+    #[test]
+    fn test_query_unrelated_change() {
+        let mut world = World::new();
+        let a = world.spawn(Score(1)).id();
+        world.spawn(Score(5));
+        let list = spawn_list(&mut world);
+        update(&mut world);
+        let before = rows(&world, list);
+
+        world.entity_mut(a).insert(Other);
+        update(&mut world);
+        assert_eq!(rows(&world, list), before);
+    }
+
+    #[test]
+    fn test_query_sort_key_moves_row() {
+        let mut world = World::new();
+        world.spawn(Score(1));
+        let b = world.spawn(Score(5)).id();
+        let list = spawn_list(&mut world);
+        update(&mut world);
+        let before = rows(&world, list);
+
+        world.get_mut::<Score>(b).unwrap().0 = 0;
+        update(&mut world);
+        let after = rows(&world, list);
+        assert_eq!(after[0].0, before[1].0);
+        assert_eq!(after[1], before[0]);
+        assert_eq!(world.resource::<Moves>().0, vec![(1, 0)]);
+    }
+
+    #[derive(Component)]
+    struct Value(i32);
+
+    /// Spawn a list of the items in a vector. Each row holds a single child with the item's value.
+    fn spawn_vec_list(world: &mut World, items: Vec<i32>) -> (Entity, MutableVec<i32>) {
+        let vec = world.create_mutable_vec(items);
+        let list = world
+            .spawn(For::each_vec(
+                vec,
+                |item: &i32, builder| {
+                    builder.spawn(Value(*item));
+                },
+                |_| {},
+            ))
+            .id();
+        update(world);
+        (list, vec)
+    }
+
+    /// The row ghost nodes of a list, and the value shown by each row.
+    fn vec_rows(world: &World, list: Entity) -> Vec<(Entity, i32)> {
+        rows(world, list)
+            .into_iter()
+            .map(|(row, child)| (row, world.get::<Value>(child).unwrap().0))
+            .collect()
+    }
+
+    #[test]
+    fn test_vec_insert_remove() {
+        let mut world = World::new();
+        let (list, vec) = spawn_vec_list(&mut world, vec![1, 2, 3]);
+        let before = vec_rows(&world, list);
+
+        vec.insert(&mut world, 1, 4);
+        vec.remove(&mut world, 3);
+        update(&mut world);
+        let after = vec_rows(&world, list);
+        assert_eq!(after.len(), 3);
+        assert_eq!(after[0], before[0]);
+        assert_eq!(after[1].1, 4);
+        assert_eq!(after[2], before[1]);
+        assert!(world.get_entity(before[2].0).is_err());
+    }
+
+    #[test]
+    fn test_vec_swap_move() {
+        let mut world = World::new();
+        let (list, vec) = spawn_vec_list(&mut world, vec![1, 2, 3]);
+        let before = vec_rows(&world, list);
+
+        vec.swap(&mut world, 0, 2);
+        update(&mut world);
+        assert_eq!(
+            vec_rows(&world, list),
+            vec![before[2], before[1], before[0]]
+        );
+
+        vec.move_item(&mut world, 0, 2);
+        update(&mut world);
+        assert_eq!(
+            vec_rows(&world, list),
+            vec![before[1], before[0], before[2]]
+        );
+    }
+
+    #[test]
+    fn test_vec_replace() {
+        let mut world = World::new();
+        let (list, vec) = spawn_vec_list(&mut world, vec![1, 2]);
+        let before = vec_rows(&world, list);
+
+        vec.set(&mut world, 0, 5);
+        update(&mut world);
+        let after = vec_rows(&world, list);
+        assert_ne!(after[0].0, before[0].0);
+        assert_eq!(after[0].1, 5);
+        assert_eq!(after[1], before[1]);
+        assert!(world.get_entity(before[0].0).is_err());
+    }
+
+    #[derive(Resource)]
+    struct Keys(Vec<u32>);
+
+    #[derive(Component)]
+    struct StateRef(Mutable<u32>);
+
+    #[test]
+    fn test_state_survives_rebuild() {
+        let mut world = World::new();
+        world.insert_resource(Keys(vec![1, 2]));
+        let list = world
+            .spawn(For::each_with_state(
+                |keys: Res<Keys>| keys.0.clone(),
+                |key| *key,
+                |key| *key * 10,
+                |_, state: Mutable<u32>, builder| {
+                    builder.spawn(StateRef(state));
+                },
+                |_| {},
+            ))
+            .id();
+        update(&mut world);
+        let before = rows(&world, list);
+        let state = |world: &World, child: Entity| world.get::<StateRef>(child).unwrap().0;
+        let (first, second) = (state(&world, before[0].1), state(&world, before[1].1));
+        first.set(&mut world, 11);
+
+        // Reordering rebuilds one of the rows, but the state follows the key.
+        world.resource_mut::<Keys>().0 = vec![2, 1];
+        update(&mut world);
+        let after = rows(&world, list);
+        assert!(after.iter().any(|row| !before.contains(row)));
+        assert_eq!(state(&world, after[0].1), second);
+        assert_eq!(state(&world, after[1].1), first);
+        assert_eq!(first.get(&world), 11);
+
+        // The state is despawned once its key is gone.
+        world.resource_mut::<Keys>().0 = vec![2];
+        update(&mut world);
+        assert!(world.get_entity(first.id()).is_err());
+    }
+}
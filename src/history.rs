@@ -0,0 +1,367 @@
+use std::{any::Any, collections::VecDeque};
+
+use bevy::prelude::*;
+
+use crate::mutable::{Mutable, WriteMutable};
+
+/// A type-erased mutable value.
+type BoxedValue = Box<dyn Any + Send + Sync>;
+
+/// Resource which records changes to tracked mutables, so that they can be undone. Changes are
+/// only recorded for mutables which have opted in with [`Mutable::track_history`], and only
+/// while this resource exists.
+///
+/// Each change is recorded as a separate step, unless it is made between
+/// [`MutableHistory::begin_group`] and [`MutableHistory::end_group`], in which case all of the
+/// changes are undone together. Use [`UndoCommand`] and [`RedoCommand`] to move through the
+/// history.
+#[derive(Resource)]
+pub struct MutableHistory {
+    undo: VecDeque<Vec<HistoryEntry>>,
+    redo: Vec<Vec<HistoryEntry>>,
+    group: Vec<HistoryEntry>,
+    group_depth: usize,
+    depth: usize,
+}
+
+impl Default for MutableHistory {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl MutableHistory {
+    /// Constructs a new history which keeps at most `depth` undo steps.
+    pub fn new(depth: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: Vec::new(),
+            group_depth: 0,
+            depth,
+        }
+    }
+
+    /// Start grouping changes into a single undo step. Groups can be nested; the step is
+    /// recorded when the outermost group ends.
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    /// Finish a group started with [`MutableHistory::begin_group`].
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth == 0 && !self.group.is_empty() {
+            let group = std::mem::take(&mut self.group);
+            self.push_undo(group);
+        }
+    }
+
+    /// Returns true if there is a step which can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there is a step which can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Discard all recorded steps.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group.clear();
+    }
+
+    /// Record a change, which invalidates the redo steps.
+    pub(crate) fn record(&mut self, entry: HistoryEntry) {
+        self.redo.clear();
+        if self.group_depth > 0 {
+            self.group.push(entry);
+        } else {
+            self.push_undo(vec![entry]);
+        }
+    }
+
+    fn push_undo(&mut self, step: Vec<HistoryEntry>) {
+        self.undo.push_back(step);
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+}
+
+/// Command which records a change made by a system which has no access to the
+/// [`MutableHistory`]. Does nothing if the history does not exist.
+pub(crate) struct RecordHistoryCommand(pub(crate) HistoryEntry);
+
+impl Command for RecordHistoryCommand {
+    fn apply(self, world: &mut World) {
+        if let Some(mut history) = world.get_resource_mut::<MutableHistory>() {
+            history.record(self.0);
+        }
+    }
+}
+
+/// Records the previous value of a mutable.
+pub(crate) struct HistoryEntry {
+    cell: Entity,
+    value: BoxedValue,
+    swap: SwapFn,
+}
+
+/// Replaces the value of a mutable, returning the previous value.
+type SwapFn = fn(&mut World, Entity, BoxedValue) -> Option<BoxedValue>;
+
+/// Marks a mutable whose changes are recorded in the [`MutableHistory`].
+#[derive(Component, Clone, Copy)]
+pub(crate) struct TrackedMutable {
+    snapshot: fn(&dyn Any) -> BoxedValue,
+    swap: SwapFn,
+}
+
+impl TrackedMutable {
+    fn new<T: Clone + Send + Sync + 'static>() -> Self {
+        Self {
+            snapshot: snapshot_value::<T>,
+            swap: swap_value::<T>,
+        }
+    }
+
+    /// Take a snapshot of the value of a mutable, before it is changed.
+    pub(crate) fn snapshot(&self, cell: Entity, value: &dyn Any) -> HistoryEntry {
+        HistoryEntry {
+            cell,
+            value: (self.snapshot)(value),
+            swap: self.swap,
+        }
+    }
+}
+
+fn snapshot_value<T: Clone + Send + Sync + 'static>(value: &dyn Any) -> BoxedValue {
+    Box::new(value.downcast_ref::<T>().unwrap().clone())
+}
+
+fn swap_value<T: Send + Sync + 'static>(
+    world: &mut World,
+    cell: Entity,
+    value: BoxedValue,
+) -> Option<BoxedValue> {
+    let value = *value.downcast::<T>().ok()?;
+    let mut prev = None;
+    world
+        .try_update_mutable(cell, |mut current: Mut<T>| {
+            prev = Some(std::mem::replace(&mut *current, value));
+        })
+        .ok()?;
+    prev.map(|prev| Box::new(prev) as BoxedValue)
+}
+
+impl<T> Mutable<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    /// Record changes to this mutable in the [`MutableHistory`], so that they can be undone.
+    pub fn track_history(&self, commands: &mut Commands) {
+        commands
+            .entity(self.cell)
+            .insert(TrackedMutable::new::<T>());
+    }
+}
+
+/// Which way to move through the history.
+#[derive(Clone, Copy)]
+enum Direction {
+    Undo,
+    Redo,
+}
+
+/// Swap the values recorded in a step with the current values, and move the step to the other
+/// stack.
+fn apply_step(world: &mut World, direction: Direction) {
+    // Remove the history while the step is applied, so that the changes are not recorded.
+    let Some(mut history) = world.remove_resource::<MutableHistory>() else {
+        return;
+    };
+    let step = match direction {
+        Direction::Undo => history.undo.pop_back(),
+        Direction::Redo => history.redo.pop(),
+    };
+    if let Some(step) = step {
+        // Entries are applied in reverse, and the inverse step is built in the order they were
+        // applied, so that applying it in reverse restores the values again. Entries for
+        // mutables which have since been despawned are dropped.
+        let inverse: Vec<HistoryEntry> = step
+            .into_iter()
+            .rev()
+            .filter_map(|entry| {
+                let value = (entry.swap)(world, entry.cell, entry.value)?;
+                Some(HistoryEntry { value, ..entry })
+            })
+            .collect();
+        if !inverse.is_empty() {
+            match direction {
+                Direction::Undo => history.redo.push(inverse),
+                Direction::Redo => history.push_undo(inverse),
+            }
+        }
+    }
+    world.insert_resource(history);
+}
+
+/// Command which undoes the most recent step in the [`MutableHistory`].
+pub struct UndoCommand;
+
+impl Command for UndoCommand {
+    fn apply(self, world: &mut World) {
+        apply_step(world, Direction::Undo);
+    }
+}
+
+/// Command which redoes the most recently undone step in the [`MutableHistory`].
+pub struct RedoCommand;
+
+impl Command for RedoCommand {
+    fn apply(self, world: &mut World) {
+        apply_step(world, Direction::Redo);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, prelude::*};
+
+    use super::*;
+    use crate::{mutable::Mutables, CreateMutable};
+
+    fn setup(depth: usize) -> (World, Mutable<i32>) {
+        let mut world = World::new();
+        world.insert_resource(MutableHistory::new(depth));
+        let mutable = world.create_mutable(0);
+        mutable.track_history(&mut world.commands());
+        world.flush();
+        (world, mutable)
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (mut world, mutable) = setup(10);
+        mutable.set(&mut world, 1);
+        mutable.set(&mut world, 2);
+        assert!(world.resource::<MutableHistory>().can_undo());
+        assert!(!world.resource::<MutableHistory>().can_redo());
+
+        UndoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 1);
+        UndoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 0);
+        assert!(!world.resource::<MutableHistory>().can_undo());
+
+        RedoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 1);
+        RedoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 2);
+        assert!(!world.resource::<MutableHistory>().can_redo());
+    }
+
+    #[test]
+    fn test_record_clears_redo() {
+        let (mut world, mutable) = setup(10);
+        mutable.set(&mut world, 1);
+        UndoCommand.apply(&mut world);
+        assert!(world.resource::<MutableHistory>().can_redo());
+        mutable.set(&mut world, 3);
+        assert!(!world.resource::<MutableHistory>().can_redo());
+    }
+
+    #[test]
+    fn test_untracked() {
+        let mut world = World::new();
+        world.init_resource::<MutableHistory>();
+        let mutable = world.create_mutable(0);
+        mutable.set(&mut world, 1);
+        assert!(!world.resource::<MutableHistory>().can_undo());
+    }
+
+    #[test]
+    fn test_group() {
+        let (mut world, mutable) = setup(10);
+        world.resource_mut::<MutableHistory>().begin_group();
+        mutable.set(&mut world, 1);
+        world.resource_mut::<MutableHistory>().begin_group();
+        mutable.set(&mut world, 2);
+        world.resource_mut::<MutableHistory>().end_group();
+        // The inner group does not record a step.
+        assert!(!world.resource::<MutableHistory>().can_undo());
+        world.resource_mut::<MutableHistory>().end_group();
+
+        UndoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 0);
+        assert!(!world.resource::<MutableHistory>().can_undo());
+        RedoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 2);
+    }
+
+    #[test]
+    fn test_depth() {
+        let (mut world, mutable) = setup(2);
+        for value in 1..=3 {
+            mutable.set(&mut world, value);
+        }
+        UndoCommand.apply(&mut world);
+        UndoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 1);
+        assert!(!world.resource::<MutableHistory>().can_undo());
+    }
+
+    #[test]
+    fn test_transaction_is_one_step() {
+        let (mut world, a) = setup(10);
+        let b = world.create_mutable(0);
+        b.track_history(&mut world.commands());
+        world.flush();
+        world
+            .transaction(|tx| {
+                tx.set(a, 1);
+                tx.set(b, 2);
+            })
+            .unwrap();
+
+        UndoCommand.apply(&mut world);
+        assert_eq!((a.get(&world), b.get(&world)), (0, 0));
+        assert!(!world.resource::<MutableHistory>().can_undo());
+        RedoCommand.apply(&mut world);
+        assert_eq!((a.get(&world), b.get(&world)), (1, 2));
+    }
+
+    #[test]
+    fn test_transaction_from_system_is_one_step() {
+        let (mut world, a) = setup(10);
+        world
+            .run_system_once(move |mut mutables: Mutables<i32>| {
+                mutables
+                    .transaction(|tx| {
+                        tx.set(a, 1);
+                        tx.update(a, |mut value| *value += 1);
+                    })
+                    .unwrap();
+            })
+            .unwrap();
+        assert_eq!(a.get(&world), 2);
+        UndoCommand.apply(&mut world);
+        assert_eq!(a.get(&world), 0);
+        assert!(!world.resource::<MutableHistory>().can_undo());
+    }
+
+    #[test]
+    fn test_record_from_system() {
+        let (mut world, mutable) = setup(10);
+        world
+            .run_system_once(move |mut mutables: Mutables<i32>| mutable.set(&mut mutables, 1))
+            .unwrap();
+        assert_eq!(mutable.get(&world), 1);
+        UndoCommand.apply(&mut world);
+        assert_eq!(mutable.get(&world), 0);
+    }
+}
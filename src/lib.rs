@@ -1,16 +1,47 @@
+mod bind;
 mod children;
 mod cond;
+mod context;
 mod effect;
+mod for_tree;
 mod foreach;
+mod history;
 mod lcs;
+mod memo;
 mod mutable;
+mod mutable_vec;
+mod persist;
+mod reconcile;
+mod reflect;
+mod resource;
+mod signal;
+mod store;
 mod switch;
+mod transaction;
+mod virtual_for;
 mod with_effect;
 
+pub use bind::{BindDirection, ComponentLens, EntityBind, Lens};
 pub use children::{BuildChildrenFn, ChildTuple, WithChildren, WithChildrenCommand};
 pub use cond::Cond;
+pub use context::{Contexts, Provide, UseContext};
+pub use do_you_believe_macros::Store;
 pub use effect::{EffectCell, EffectPlugin};
-pub use foreach::For;
-pub use mutable::{CreateMutable, Mutable};
+pub use for_tree::{ForTree, TreeNode};
+pub use foreach::{For, ForQuery, ItemInserted, ItemMoved, ItemRemoved};
+pub use history::{MutableHistory, RedoCommand, UndoCommand};
+pub use memo::{CreateMemo, Memo};
+pub use mutable::{
+    CreateMutable, CreateMutableIn, Mutable, MutableChanged, MutableError, MutableScope, Mutables,
+    ReadMutable, WriteMutable,
+};
+pub use mutable_vec::MutableVec;
+pub use persist::{PersistPlugin, PersistSettings};
+pub use reflect::{ReflectMutable, RegisterMutable};
+pub use resource::MirrorResource;
+pub use signal::Signal;
+pub use store::Store;
 pub use switch::Switch;
+pub use transaction::Transaction;
+pub use virtual_for::VirtualFor;
 pub use with_effect::{EntityWithEffect, WithEffect};
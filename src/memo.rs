@@ -0,0 +1,266 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    ecs::{system::SystemId, world::DeferredWorld},
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{
+    effect::{AnyEffect, EffectCell, UnregisterSystemCommand},
+    mutable::{MutableCell, MutableError, ReadMutable, WriteMutable},
+    Mutable, Signal,
+};
+
+/// Contains a reference to a memoized value, which is derived from other reactive values.
+/// The value is stored in an entity, in the same way as a [`Mutable`], and is recomputed only
+/// when its dependencies change.
+///
+/// If the dependencies can't be computed yet, for example because they read a resource which
+/// doesn't exist, the memo has no value until the first update in which they can. Reading it
+/// before then returns an error from the `try_` accessors.
+#[derive(PartialEq, Debug)]
+pub struct Memo<T> {
+    /// The mutable cell that holds the cached value.
+    pub(crate) inner: Mutable<T>,
+}
+
+impl<T> Memo<T> {
+    /// The entity that holds the cached value.
+    pub fn id(&self) -> Entity {
+        self.inner.id()
+    }
+}
+
+impl<T> Copy for Memo<T> {}
+impl<T> Clone for Memo<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Memo<T>
+where
+    T: PartialEq + Send + Sync + 'static,
+{
+    /// Returns a signal for this [`Memo`].
+    pub fn signal(&self) -> Signal<T> {
+        Signal::Memo(*self)
+    }
+
+    /// Get a reference to the value of this [`Memo`].
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn as_ref<'a, 'b: 'a, R: ReadMutable>(&'a self, cx: &'b R) -> &'a T {
+        cx.read_mutable_as_ref(&self.inner)
+    }
+
+    /// Read the value of this [`Memo`] using a mapping function.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    /// * `f`: The mapping function.
+    pub fn map<R: ReadMutable, U, F: Fn(&T) -> U>(&self, cx: &R, f: F) -> U {
+        cx.read_mutable_map(&self.inner, f)
+    }
+}
+
+impl<T> Memo<T>
+where
+    T: PartialEq + Copy + Send + Sync + 'static,
+{
+    /// Get the value of this [`Memo`] with Copy semantics.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn get<R: ReadMutable>(&self, cx: &R) -> T {
+        cx.read_mutable(&self.inner)
+    }
+
+    /// Get the value of this [`Memo`] with Copy semantics, or return an error if it has no value.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_get<R: ReadMutable>(&self, cx: &R) -> Result<T, MutableError> {
+        cx.try_read_mutable(&self.inner)
+    }
+}
+
+impl<T> Memo<T>
+where
+    T: PartialEq + Clone + Send + Sync + 'static,
+{
+    /// Get the value of this [`Memo`] with Clone semantics.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn get_clone<R: ReadMutable>(&self, cx: &R) -> T {
+        cx.read_mutable_clone(&self.inner)
+    }
+
+    /// Get the value of this [`Memo`] with Clone semantics, or return an error if it has no
+    /// value.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_get_clone<R: ReadMutable>(&self, cx: &R) -> Result<T, MutableError> {
+        cx.try_read_mutable_clone(&self.inner)
+    }
+}
+
+/// Trait for creating new memoized values.
+pub trait CreateMemo {
+    /// Create a new [`Memo`]. When created through a [`ChildBuilder`], the memo is computed by a
+    /// command, so it can't be read until the commands have been applied.
+    ///
+    /// Arguments:
+    /// * `deps_fn`: A system which returns the dependencies of the computation, typically by
+    ///   reading mutables or resources. This runs every frame, so it should be cheap.
+    /// * `compute`: Computes the value from the dependencies. This only runs when the result of
+    ///   `deps_fn` changes.
+    fn create_memo<
+        T: PartialEq + Send + Sync + 'static,
+        P: PartialEq + Clone + Send + Sync + 'static,
+        M: Send + Sync + 'static,
+        DepsFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+        ComputeFn: Fn(P) -> T + Send + Sync + 'static,
+    >(
+        &mut self,
+        deps_fn: DepsFn,
+        compute: ComputeFn,
+    ) -> Memo<T>;
+}
+
+impl CreateMemo for ChildBuilder<'_> {
+    fn create_memo<
+        T: PartialEq + Send + Sync + 'static,
+        P: PartialEq + Clone + Send + Sync + 'static,
+        M: Send + Sync + 'static,
+        DepsFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+        ComputeFn: Fn(P) -> T + Send + Sync + 'static,
+    >(
+        &mut self,
+        deps_fn: DepsFn,
+        compute: ComputeFn,
+    ) -> Memo<T> {
+        let cell = self.spawn(GhostNode::default()).id();
+        self.enqueue_command(CreateMemoCommand {
+            cell,
+            deps_fn,
+            compute,
+            marker: PhantomData::<(T, P, M)>,
+        });
+        Memo {
+            inner: Mutable {
+                cell,
+                marker: PhantomData,
+            },
+        }
+    }
+}
+
+impl CreateMemo for World {
+    fn create_memo<
+        T: PartialEq + Send + Sync + 'static,
+        P: PartialEq + Clone + Send + Sync + 'static,
+        M: Send + Sync + 'static,
+        DepsFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+        ComputeFn: Fn(P) -> T + Send + Sync + 'static,
+    >(
+        &mut self,
+        deps_fn: DepsFn,
+        compute: ComputeFn,
+    ) -> Memo<T> {
+        let cell = self.spawn(GhostNode::default()).id();
+        CreateMemoCommand {
+            cell,
+            deps_fn,
+            compute,
+            marker: PhantomData::<(T, P, M)>,
+        }
+        .apply(self);
+        Memo {
+            inner: Mutable {
+                cell,
+                marker: PhantomData,
+            },
+        }
+    }
+}
+
+/// Command which computes the initial value of a [`Memo`], and attaches the effect which keeps
+/// it up to date.
+struct CreateMemoCommand<T, P, M, DepsFn, ComputeFn> {
+    cell: Entity,
+    deps_fn: DepsFn,
+    compute: ComputeFn,
+    marker: PhantomData<(T, P, M)>,
+}
+
+impl<
+        T: PartialEq + Send + Sync + 'static,
+        P: PartialEq + Clone + Send + Sync + 'static,
+        M: Send + Sync + 'static,
+        DepsFn: IntoSystem<(), P, M> + Send + Sync + 'static,
+        ComputeFn: Fn(P) -> T + Send + Sync + 'static,
+    > Command for CreateMemoCommand<T, P, M, DepsFn, ComputeFn>
+{
+    fn apply(self, world: &mut World) {
+        let deps_sys = world.register_system(self.deps_fn);
+        let mut effect = MemoEffect {
+            deps: None,
+            deps_sys,
+            compute: self.compute,
+            marker: PhantomData::<T>,
+        };
+        // Compute the initial value now if possible. Otherwise the effect will compute it once
+        // the dependencies can be read.
+        effect.update(world, self.cell);
+        world
+            .entity_mut(self.cell)
+            .insert(EffectCell(Arc::new(Mutex::new(effect))));
+    }
+}
+
+/// Effect which recomputes the value of a [`Memo`] when its dependencies change.
+struct MemoEffect<T, P, ComputeFn: Fn(P) -> T> {
+    /// The dependencies from the last successful run, or `None` if they have never been read,
+    /// in which case the memo has no value yet.
+    deps: Option<P>,
+    deps_sys: SystemId<(), P>,
+    compute: ComputeFn,
+    marker: PhantomData<T>,
+}
+
+impl<
+        T: PartialEq + Send + Sync + 'static,
+        P: PartialEq + Clone + 'static,
+        ComputeFn: Fn(P) -> T,
+    > AnyEffect for MemoEffect<T, P, ComputeFn>
+{
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        // Run the dependencies and see if the result changed.
+        let Ok(deps) = world.run_system(self.deps_sys) else {
+            return;
+        };
+        if self.deps.as_ref() == Some(&deps) {
+            return;
+        }
+        let value = (self.compute)(deps.clone());
+        if self.deps.replace(deps).is_some() {
+            world.write_mutable(entity, value);
+        } else {
+            world.entity_mut(entity).insert(MutableCell::new(value));
+        }
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        world
+            .commands()
+            .queue(UnregisterSystemCommand(self.deps_sys));
+    }
+}
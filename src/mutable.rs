@@ -1,10 +1,123 @@
-use std::marker::PhantomData;
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+};
 
-use bevy::{ecs::world::DeferredWorld, prelude::*, ui::experimental::GhostNode};
+use bevy::{
+    ecs::{
+        component::{ComponentId, Tick},
+        query::QueryEntityError,
+        system::SystemParam,
+        world::DeferredWorld,
+    },
+    prelude::*,
+    ui::experimental::GhostNode,
+};
 
-/// Contains a mutable reactive value.
-#[derive(Component)]
-pub(crate) struct MutableCell<T>(pub(crate) T);
+use crate::{
+    history::{HistoryEntry, MutableHistory, RecordHistoryCommand, TrackedMutable},
+    MutableVec, Signal, Store, Transaction,
+};
+
+/// Contains a mutable reactive value. This is reflectable when `T` is; see
+/// [`crate::RegisterMutable`], which also registers `ReflectComponent` data that writes the
+/// value in the same way as [`crate::ReflectMutable`].
+#[derive(Component, Reflect)]
+#[require(AnyMutableCell)]
+pub(crate) struct MutableCell<T>(pub(crate) T, #[reflect(ignore)] pub(crate) CellMeta<T>);
+
+impl<T> MutableCell<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Self(value, CellMeta::default())
+    }
+
+    pub(crate) fn with_eq(value: T, eq: fn(&T, &T) -> bool) -> Self {
+        Self(
+            value,
+            CellMeta {
+                eq: Some(eq),
+                ..default()
+            },
+        )
+    }
+}
+
+/// Bookkeeping which is stored alongside the value of a [`MutableCell`].
+pub(crate) struct CellMeta<T> {
+    /// Incremented each time the value changes.
+    pub(crate) version: u64,
+
+    /// Comparison which is used instead of `PartialEq` to decide whether a write changes the
+    /// value. A comparison which always returns false makes every write a change.
+    pub(crate) eq: Option<fn(&T, &T) -> bool>,
+
+    /// Set when the value was changed through `ReflectComponent::apply`, which has no access to
+    /// the world, until [`MutableChanged`] has been triggered. Holds the history entry for the
+    /// change if the mutable is tracked.
+    pub(crate) reflected: Option<Option<HistoryEntry>>,
+}
+
+impl<T> Default for CellMeta<T> {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            eq: None,
+            reflected: None,
+        }
+    }
+}
+
+/// Marker which is present on every [`MutableCell`], regardless of type.
+#[derive(Component, Default)]
+pub(crate) struct AnyMutableCell;
+
+/// Marks an entity which owns a group of mutables that are not part of any view, such as the
+/// global state for a screen. Mutables are added to the scope with
+/// [`CreateMutableIn::create_mutable_in`]. When the scope is despawned, or this component is
+/// removed, the mutables it owns are despawned too, even if the scope itself was not despawned
+/// recursively. Combine with `StateScoped` to tie the mutables to a state.
+#[derive(Component, Default, Debug)]
+#[component(on_remove = despawn_scope_mutables)]
+pub struct MutableScope;
+
+/// Despawn the mutables owned by a [`MutableScope`] which is being removed.
+fn despawn_scope_mutables(mut world: DeferredWorld, scope: Entity, _: ComponentId) {
+    let Some(children) = world.get::<Children>(scope) else {
+        return;
+    };
+    let cells: Vec<Entity> = children
+        .iter()
+        .copied()
+        .filter(|child| world.get::<AnyMutableCell>(*child).is_some())
+        .collect();
+    for cell in cells {
+        // The cell may already be gone if the scope is being despawned recursively.
+        world.commands().queue(move |world: &mut World| {
+            if let Ok(cell) = world.get_entity_mut(cell) {
+                cell.despawn_recursive();
+            }
+        });
+    }
+}
+
+/// Entity event which is triggered on a mutable's cell when a write changes its value. Writes of
+/// an equal value, and updates which don't modify the value, do not trigger this event.
+#[derive(Event)]
+pub struct MutableChanged<T: Send + Sync + 'static> {
+    /// The mutable which changed.
+    pub mutable: Mutable<T>,
+}
+
+impl<T: Send + Sync + 'static> MutableChanged<T> {
+    pub(crate) fn new(mutable: Entity) -> Self {
+        Self {
+            mutable: Mutable {
+                cell: mutable,
+                marker: PhantomData,
+            },
+        }
+    }
+}
 
 /// Contains a reference to a reactive mutable variable.
 #[derive(PartialEq, Debug)]
@@ -31,11 +144,47 @@ impl<T> Clone for Mutable<T> {
 }
 
 /// Trait for creating new mutable variables.
+///
+/// Mutables created with a [`ChildBuilder`], [`EntityCommands`] or [`EntityWorldMut`] are spawned
+/// as children of the parent entity, and are despawned along with it. Mutables created with a
+/// [`World`], [`Commands`] or [`DeferredWorld`] have no owner, and must be despawned explicitly;
+/// use [`CreateMutableIn::create_mutable_in`] to give them an owner such as a [`MutableScope`].
+/// When created via commands, the cell entity is reserved immediately, so the [`Mutable`] can be
+/// used right away, but its value cannot be read until the commands are applied.
 pub trait CreateMutable {
     /// Create a new [`Mutable`].
     fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
     where
         T: Send + Sync + 'static;
+
+    /// Create a new [`Mutable`] which uses `eq` instead of `PartialEq` to decide whether a write
+    /// changes its value. Use this for types which are not `PartialEq`, or which are expensive to
+    /// compare.
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
+    where
+        T: Send + Sync + 'static;
+
+    /// Create a new [`Mutable`] for which every write counts as a change, even if the value is
+    /// the same. Readers can use [`Mutable::version`] to detect changes without comparing values.
+    fn create_mutable_always_changed<T>(&mut self, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.create_mutable_with_eq(init, |_, _| false)
+    }
+
+    /// Create a new [`MutableVec`].
+    fn create_mutable_vec<T>(&mut self, init: Vec<T>) -> MutableVec<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        MutableVec::new(self.create_mutable(MutableVec::initial_state(init)))
+    }
+
+    /// Create a new store, which holds each field of a [`Store`] struct in its own [`Mutable`].
+    fn create_store<S: Store>(&mut self, init: S) -> S::Handle {
+        init.into_store(self)
+    }
 }
 
 impl CreateMutable for ChildBuilder<'_> {
@@ -45,7 +194,22 @@ impl CreateMutable for ChildBuilder<'_> {
     {
         let parent = self.parent_entity();
         let cell = self
-            .spawn((MutableCell::<T>(init), GhostNode::default()))
+            .spawn((MutableCell::<T>::new(init), GhostNode::default()))
+            .set_parent(parent)
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let parent = self.parent_entity();
+        let cell = self
+            .spawn((MutableCell::with_eq(init, eq), GhostNode::default()))
             .set_parent(parent)
             .id();
         Mutable {
@@ -55,6 +219,154 @@ impl CreateMutable for ChildBuilder<'_> {
     }
 }
 
+impl CreateMutable for EntityCommands<'_> {
+    fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let parent = self.id();
+        let cell = self
+            .commands()
+            .spawn((MutableCell::<T>::new(init), GhostNode::default()))
+            .set_parent(parent)
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let parent = self.id();
+        let cell = self
+            .commands()
+            .spawn((MutableCell::with_eq(init, eq), GhostNode::default()))
+            .set_parent(parent)
+            .id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl CreateMutable for EntityWorldMut<'_> {
+    fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let parent = self.id();
+        let cell = self.world_scope(|world| {
+            world
+                .spawn((MutableCell::<T>::new(init), GhostNode::default()))
+                .set_parent(parent)
+                .id()
+        });
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let parent = self.id();
+        let cell = self.world_scope(|world| {
+            world
+                .spawn((MutableCell::with_eq(init, eq), GhostNode::default()))
+                .set_parent(parent)
+                .id()
+        });
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl CreateMutable for Commands<'_, '_> {
+    fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let cell = self.spawn(MutableCell::<T>::new(init)).id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let cell = self.spawn(MutableCell::with_eq(init, eq)).id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl CreateMutable for DeferredWorld<'_> {
+    fn create_mutable<T>(&mut self, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.commands().create_mutable(init)
+    }
+
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.commands().create_mutable_with_eq(init, eq)
+    }
+}
+
+/// Trait for creating mutables which are owned by an entity other than the one being built. The
+/// owner can be any entity; the mutable is spawned as a child of it, and is despawned when it is
+/// despawned recursively. Use a [`MutableScope`] as the owner to also free the mutables when the
+/// scope is despawned non-recursively.
+pub trait CreateMutableIn {
+    /// Create a new [`Mutable`] owned by `owner`.
+    fn create_mutable_in<T>(&mut self, owner: Entity, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static;
+}
+
+impl CreateMutableIn for World {
+    fn create_mutable_in<T>(&mut self, owner: Entity, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.entity_mut(owner).create_mutable(init)
+    }
+}
+
+impl CreateMutableIn for Commands<'_, '_> {
+    fn create_mutable_in<T>(&mut self, owner: Entity, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.entity(owner).create_mutable(init)
+    }
+}
+
+impl CreateMutableIn for DeferredWorld<'_> {
+    fn create_mutable_in<T>(&mut self, owner: Entity, init: T) -> Mutable<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.commands().entity(owner).create_mutable(init)
+    }
+}
+
 impl<T> Mutable<T>
 where
     T: Send + Sync + 'static,
@@ -64,29 +376,97 @@ where
     pub fn update<W: WriteMutable, F: FnOnce(Mut<T>)>(&self, w: &mut W, updater: F) {
         w.update_mutable(self.id(), updater);
     }
+
+    /// Update a mutable value in place using a callback, or return an error if the mutable no
+    /// longer exists.
+    pub fn try_update<W: WriteMutable, F: FnOnce(Mut<T>)>(
+        &self,
+        w: &mut W,
+        updater: F,
+    ) -> Result<(), MutableError> {
+        w.try_update_mutable(self.id(), updater)
+    }
+
+    /// Replace the value of this [`Mutable`]. Unlike [`Mutable::set`], this does not require
+    /// `PartialEq`: the write counts as a change unless the mutable was created with a comparison
+    /// which says the values are equal. See [`CreateMutable::create_mutable_with_eq`].
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    /// * `value`: The new value.
+    pub fn replace<W: WriteMutable>(&self, cx: &mut W, value: T) {
+        self.try_replace(cx, value).unwrap();
+    }
+
+    /// Replace the value of this [`Mutable`], or return an error if it no longer exists. See
+    /// [`Mutable::replace`].
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    /// * `value`: The new value.
+    pub fn try_replace<W: WriteMutable>(&self, cx: &mut W, value: T) -> Result<(), MutableError> {
+        cx.try_replace_mutable(self.cell, value, |_, _| false)
+    }
+
+    /// Get the version of this [`Mutable`], which increases each time its value changes.
+    /// Comparing versions is a cheap way to detect changes without comparing values.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn version<R: ReadMutable>(&self, cx: &R) -> u64 {
+        self.try_version(cx).unwrap()
+    }
+
+    /// Get the version of this [`Mutable`], or an error if it no longer exists.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_version<R: ReadMutable>(&self, cx: &R) -> Result<u64, MutableError> {
+        cx.try_read_mutable_version(self)
+    }
+
+    /// Get a reference to the value of this [`Mutable`].
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn as_ref<'a, 'b: 'a, R: ReadMutable>(&'a self, cx: &'b R) -> &'a T {
+        cx.read_mutable_as_ref(self)
+    }
+
+    /// Get a reference to the value of this [`Mutable`], or an error if it no longer exists.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_as_ref<'a, 'b: 'a, R: ReadMutable>(
+        &'a self,
+        cx: &'b R,
+    ) -> Result<&'a T, MutableError> {
+        cx.try_read_mutable_as_ref(self)
+    }
 }
 
 impl<T> Mutable<T>
 where
     T: PartialEq + Send + Sync + 'static,
 {
-    // /// Returns a signal for this [`Mutable`] with Copy semantics.
-    // pub fn signal(&self) -> Signal<T> {
-    //     Signal::Mutable(*self)
-    // }
+    /// Returns a signal for this [`Mutable`].
+    pub fn signal(&self) -> Signal<T> {
+        Signal::Mutable(*self)
+    }
 
-    /// Get a reference to the value of this [`Mutable`].
+    /// Set the value of this [`Mutable`], or return an error if it no longer exists.
     ///
     /// Arguments:
     /// * `cx`: The reactive context.
-    pub fn as_ref<'a, 'b: 'a, R: ReadMutable>(&'a self, cx: &'b R) -> &'a T {
-        cx.read_mutable_as_ref(self)
+    /// * `value`: The new value.
+    pub fn try_set<R: WriteMutable>(&self, cx: &mut R, value: T) -> Result<(), MutableError> {
+        cx.try_write_mutable(self.cell, value)
     }
 }
 
 impl<T> Mutable<T>
 where
-    T: PartialEq + Copy + Send + Sync + 'static,
+    T: Copy + Send + Sync + 'static,
 {
     /// Get the value of this [`Mutable`] with Copy semantics.
     ///
@@ -96,19 +476,31 @@ where
         cx.read_mutable(self)
     }
 
+    /// Get the value of this [`Mutable`] with Copy semantics, or an error if it no longer
+    /// exists.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_get<R: ReadMutable>(&self, cx: &R) -> Result<T, MutableError> {
+        cx.try_read_mutable(self)
+    }
+
     /// Set the value of this [`Mutable`] with Copy semantics.
     ///
     /// Arguments:
     /// * `cx`: The reactive context.
     /// * `value`: The new value.
-    pub fn set<R: WriteMutable>(&self, cx: &mut R, value: T) {
+    pub fn set<R: WriteMutable>(&self, cx: &mut R, value: T)
+    where
+        T: PartialEq,
+    {
         cx.write_mutable(self.cell, value);
     }
 }
 
 impl<T> Mutable<T>
 where
-    T: PartialEq + Clone + Send + Sync + 'static,
+    T: Clone + Send + Sync + 'static,
 {
     /// Get the value of this [`Mutable`] with Clone semantics.
     ///
@@ -118,111 +510,381 @@ where
         cx.read_mutable_clone(self)
     }
 
+    /// Get the value of this [`Mutable`] with Clone semantics, or an error if it no longer
+    /// exists.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_get_clone<R: ReadMutable>(&self, cx: &R) -> Result<T, MutableError> {
+        cx.try_read_mutable_clone(self)
+    }
+
     /// Set the value of this [`Mutable`] with Clone semantics.
     ///
     /// Arguments:
     /// * `cx`: The reactive context.
     /// * `value`: The new value.
-    pub fn set_clone<R: WriteMutable>(&self, cx: &mut R, value: T) {
+    pub fn set_clone<R: WriteMutable>(&self, cx: &mut R, value: T)
+    where
+        T: PartialEq,
+    {
         cx.write_mutable(self.cell, value);
     }
 }
 
+/// Error returned when a mutable cannot be accessed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MutableError {
+    /// The entity which holds the mutable does not exist, usually because it was despawned.
+    NoEntity(Entity),
+    /// The entity exists, but does not hold a mutable of the requested type.
+    WrongType(Entity),
+    /// The context only has access to mutables, and cannot read other state from the world.
+    NoWorld,
+}
+
+impl MutableError {
+    /// Work out why the cell for `mutable` could not be found.
+    pub(crate) fn missing(world: &World, mutable: Entity) -> Self {
+        if world.entities().contains(mutable) {
+            MutableError::WrongType(mutable)
+        } else {
+            MutableError::NoEntity(mutable)
+        }
+    }
+}
+
+impl std::fmt::Display for MutableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MutableError::NoEntity(entity) => write!(f, "mutable entity {entity} does not exist"),
+            MutableError::WrongType(entity) => {
+                write!(
+                    f,
+                    "entity {entity} does not hold a mutable of the requested type"
+                )
+            }
+            MutableError::NoWorld => write!(f, "the world cannot be read from this context"),
+        }
+    }
+}
+
+impl std::error::Error for MutableError {}
+
 /// Trait for low-level read-access to mutables given an entity id.
 pub trait ReadMutable {
+    /// Read the value of a mutable variable using a mapping function, or return an error if the
+    /// mutable does not exist.
+    fn try_read_mutable_map<T, U, F: Fn(&T) -> U>(
+        &self,
+        mutable: &Mutable<T>,
+        f: F,
+    ) -> Result<U, MutableError>
+    where
+        T: Send + Sync + 'static;
+
+    /// Return an immutable reference to the mutable variable, or an error if the mutable does not
+    /// exist.
+    fn try_read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> Result<&T, MutableError>
+    where
+        T: Send + Sync + 'static;
+
+    /// Return the version of the mutable variable, which increases each time its value changes,
+    /// or an error if the mutable does not exist.
+    fn try_read_mutable_version<T>(&self, mutable: &Mutable<T>) -> Result<u64, MutableError>
+    where
+        T: Send + Sync + 'static;
+
+    /// Read a value from the world, such as a resource, using an accessor function. Returns
+    /// [`MutableError::NoWorld`] if this context does not have access to the world, which is the
+    /// default.
+    fn try_read_world<U>(&self, _f: fn(&World) -> U) -> Result<U, MutableError> {
+        Err(MutableError::NoWorld)
+    }
+
+    /// Read the value of a mutable variable using Copy semantics, or return an error if the
+    /// mutable does not exist.
+    fn try_read_mutable<T>(&self, mutable: &Mutable<T>) -> Result<T, MutableError>
+    where
+        T: Send + Sync + Copy + 'static,
+    {
+        self.try_read_mutable_map(mutable, |value| *value)
+    }
+
+    /// Read the value of a mutable variable using Clone semantics, or return an error if the
+    /// mutable does not exist.
+    fn try_read_mutable_clone<T>(&self, mutable: &Mutable<T>) -> Result<T, MutableError>
+    where
+        T: Send + Sync + Clone + 'static,
+    {
+        self.try_read_mutable_map(mutable, |value| value.clone())
+    }
+
     /// Read the value of a mutable variable using Copy semantics. Calling this function adds the
     /// mutable to the current tracking scope.
     fn read_mutable<T>(&self, mutable: &Mutable<T>) -> T
     where
-        T: Send + Sync + Copy + 'static;
+        T: Send + Sync + Copy + 'static,
+    {
+        self.try_read_mutable(mutable).unwrap()
+    }
 
     /// Read the value of a mutable variable using Clone semantics. Calling this function adds the
     /// mutable to the current tracking scope.
     fn read_mutable_clone<T>(&self, mutable: &Mutable<T>) -> T
     where
-        T: Send + Sync + Clone + 'static;
+        T: Send + Sync + Clone + 'static,
+    {
+        self.try_read_mutable_clone(mutable).unwrap()
+    }
 
     /// Return an immutable reference to the mutable variable.
     fn read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> &T
     where
-        T: Send + Sync + 'static;
+        T: Send + Sync + 'static,
+    {
+        self.try_read_mutable_as_ref(mutable).unwrap()
+    }
 
     /// Read the value of a mutable variable using a mapping function.
     fn read_mutable_map<T, U, F: Fn(&T) -> U>(&self, mutable: &Mutable<T>, f: F) -> U
     where
-        T: Send + Sync + 'static;
+        T: Send + Sync + 'static,
+    {
+        self.try_read_mutable_map(mutable, f).unwrap()
+    }
 }
 
 /// Trait for low-level write-access to mutables given an entity id.
 pub trait WriteMutable {
+    /// Update a mutable value in place using a callback, without triggering [`MutableChanged`].
+    /// Returns whether the value changed, or an error if the mutable does not exist. The callback
+    /// is passed a `Mut<T>` which can be used to modify the value, along with the comparison the
+    /// mutable was created with, if any. If the value changed, the caller must call
+    /// [`WriteMutable::trigger_mutable_changed`] once it is safe for observers to run.
+    fn try_update_mutable_cell_silently<T, F: FnOnce(Mut<T>, Option<fn(&T, &T) -> bool>)>(
+        &mut self,
+        mutable: Entity,
+        updater: F,
+    ) -> Result<bool, MutableError>
+    where
+        T: Send + Sync + 'static;
+
+    /// Trigger [`MutableChanged`] on a mutable whose value was changed silently.
+    fn trigger_mutable_changed<T>(&mut self, mutable: Entity)
+    where
+        T: Send + Sync + 'static;
+
+    /// Start grouping the changes recorded in the [`MutableHistory`] into a single undo step, if
+    /// this context records history. See [`MutableHistory::begin_group`].
+    fn begin_history_group(&mut self) {}
+
+    /// Finish a group started with [`WriteMutable::begin_history_group`].
+    fn end_history_group(&mut self) {}
+
+    /// Update a mutable value in place using a callback, or return an error if the mutable does
+    /// not exist. The callback is passed a `Mut<T>` which can be used to modify the value, along
+    /// with the comparison the mutable was created with, if any.
+    fn try_update_mutable_cell<T, F: FnOnce(Mut<T>, Option<fn(&T, &T) -> bool>)>(
+        &mut self,
+        mutable: Entity,
+        updater: F,
+    ) -> Result<(), MutableError>
+    where
+        T: Send + Sync + 'static,
+    {
+        if self.try_update_mutable_cell_silently(mutable, updater)? {
+            self.trigger_mutable_changed::<T>(mutable);
+        }
+        Ok(())
+    }
+
+    /// Update a mutable value in place using a callback, or return an error if the mutable does
+    /// not exist. The callback is passed a `Mut<T>` which can be used to modify the value.
+    fn try_update_mutable<T, F: FnOnce(Mut<T>)>(
+        &mut self,
+        mutable: Entity,
+        updater: F,
+    ) -> Result<(), MutableError>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.try_update_mutable_cell(mutable, |value, _| updater(value))
+    }
+
+    /// Replace the value of a mutable variable, or return an error if the mutable does not exist.
+    /// Does nothing if the values are equal according to the comparison the mutable was created
+    /// with, or `eq` if it has none.
+    fn try_replace_mutable<T>(
+        &mut self,
+        mutable: Entity,
+        value: T,
+        eq: fn(&T, &T) -> bool,
+    ) -> Result<(), MutableError>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.try_update_mutable_cell(mutable, |mut cell: Mut<T>, cell_eq| {
+            if !cell_eq.unwrap_or(eq)(&cell, &value) {
+                *cell = value;
+            }
+        })
+    }
+
+    /// Write the value of a mutable variable, or return an error if the mutable does not exist.
+    /// Does nothing if the value being set matches the existing value.
+    fn try_write_mutable<T>(&mut self, mutable: Entity, value: T) -> Result<(), MutableError>
+    where
+        T: Send + Sync + PartialEq + 'static,
+    {
+        self.try_replace_mutable(mutable, value, T::eq)
+    }
+
     /// Write the value of a mutable variable using Copy semantics. Does nothing if
     /// the value being set matches the existing value.
     fn write_mutable<T>(&mut self, mutable: Entity, value: T)
     where
-        T: Send + Sync + PartialEq + 'static;
+        T: Send + Sync + PartialEq + 'static,
+    {
+        self.try_write_mutable(mutable, value).unwrap();
+    }
 
     /// Update a mutable value in place using a callback. The callback is passed a
     /// `Mut<T>` which can be used to modify the value.
     fn update_mutable<T, F: FnOnce(Mut<T>)>(&mut self, mutable: Entity, updater: F)
     where
-        T: Send + Sync + 'static;
-}
+        T: Send + Sync + 'static,
+    {
+        self.try_update_mutable(mutable, updater).unwrap();
+    }
 
-impl ReadMutable for World {
-    fn read_mutable<T>(&self, mutable: &Mutable<T>) -> T
+    /// Stage a set of writes and apply them together. [`MutableChanged`] is only triggered once
+    /// every write has been applied, so observers and effects never see a partially updated
+    /// state. If any mutable in the transaction does not exist when it commits, an error is
+    /// returned and none of the writes are applied. The writes are recorded in the
+    /// [`MutableHistory`] as a single undo step.
+    ///
+    /// Arguments:
+    /// * `f`: Callback which stages writes on the [`Transaction`].
+    fn transaction<'a, F>(&mut self, f: F) -> Result<(), MutableError>
     where
-        T: Send + Sync + Copy + 'static,
+        Self: ReadMutable + Sized,
+        F: FnOnce(&mut Transaction<'_, 'a, Self>),
     {
-        let mutable_entity = self.entity(mutable.cell);
-        mutable_entity.get::<MutableCell<T>>().unwrap().0
+        let mut tx = Transaction::new(self);
+        f(&mut tx);
+        let staged = tx.into_staged();
+        self.begin_history_group();
+        let result = staged.commit(self);
+        self.end_history_group();
+        result
     }
+}
 
-    fn read_mutable_clone<T>(&self, mutable: &Mutable<T>) -> T
+/// Run an updater on the value of a mutable, and return whether it modified the value.
+fn run_updater<T, F: FnOnce(Mut<T>)>(mut value: Mut<T>, updater: F) -> bool {
+    // Rewind the change tick, so that we can tell whether the updater dereferenced the value
+    // mutably. The current tick is never earlier than the previous change, so it can't be
+    // mistaken for the rewound tick.
+    let prev = value.last_changed();
+    let rewound = Tick::new(prev.get().wrapping_sub(1));
+    value.set_last_changed(rewound);
+    (updater)(value.reborrow());
+    if value.last_changed() == rewound {
+        value.set_last_changed(prev);
+        false
+    } else {
+        true
+    }
+}
+
+/// Look up the cell for a mutable.
+fn get_cell<T: Send + Sync + 'static>(
+    world: &World,
+    mutable: Entity,
+) -> Result<&MutableCell<T>, MutableError> {
+    world
+        .get::<MutableCell<T>>(mutable)
+        .ok_or_else(|| MutableError::missing(world, mutable))
+}
+
+impl ReadMutable for World {
+    fn try_read_mutable_map<T, U, F: Fn(&T) -> U>(
+        &self,
+        mutable: &Mutable<T>,
+        f: F,
+    ) -> Result<U, MutableError>
     where
-        T: Send + Sync + Clone + 'static,
+        T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        mutable_entity.get::<MutableCell<T>>().unwrap().0.clone()
+        get_cell(self, mutable.cell).map(|cell| f(&cell.0))
     }
 
-    fn read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> &T
+    fn try_read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> Result<&T, MutableError>
     where
         T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        &mutable_entity.get::<MutableCell<T>>().unwrap().0
+        get_cell(self, mutable.cell).map(|cell| &cell.0)
     }
 
-    fn read_mutable_map<T, U, F: Fn(&T) -> U>(&self, mutable: &Mutable<T>, f: F) -> U
+    fn try_read_mutable_version<T>(&self, mutable: &Mutable<T>) -> Result<u64, MutableError>
     where
         T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        f(&mutable_entity.get::<MutableCell<T>>().unwrap().0)
+        get_cell::<T>(self, mutable.cell).map(|cell| cell.1.version)
+    }
+
+    fn try_read_world<U>(&self, f: fn(&World) -> U) -> Result<U, MutableError> {
+        Ok(f(self))
     }
 }
 
 impl WriteMutable for World {
-    /// Write the value of a mutable variable. Does nothing if the value being set matches the
-    /// existing value.
-    fn write_mutable<T>(&mut self, mutable: Entity, value: T)
+    fn try_update_mutable_cell_silently<T, F: FnOnce(Mut<T>, Option<fn(&T, &T) -> bool>)>(
+        &mut self,
+        mutable: Entity,
+        updater: F,
+    ) -> Result<bool, MutableError>
     where
-        T: Send + Sync + PartialEq + 'static,
+        T: Send + Sync + 'static,
     {
-        let mut entt = self.entity_mut(mutable);
-        let mut cell = entt.get_mut::<MutableCell<T>>().unwrap();
-        if cell.0 != value {
-            cell.0 = value;
+        let tracked = self
+            .get::<TrackedMutable>(mutable)
+            .copied()
+            .filter(|_| self.contains_resource::<MutableHistory>());
+        let Some(mut cell) = self.get_mut::<MutableCell<T>>(mutable) else {
+            return Err(MutableError::missing(self, mutable));
+        };
+        let eq = cell.1.eq;
+        let entry = tracked.map(|tracked| tracked.snapshot(mutable, &cell.0));
+        let value = cell.reborrow().map_unchanged(|v| &mut v.0);
+        if !run_updater(value, |value| updater(value, eq)) {
+            return Ok(false);
+        }
+        cell.bypass_change_detection().1.version += 1;
+        if let Some(entry) = entry {
+            self.resource_mut::<MutableHistory>().record(entry);
         }
+        Ok(true)
     }
 
-    fn update_mutable<T, F: FnOnce(Mut<T>)>(&mut self, mutable: Entity, updater: F)
+    fn trigger_mutable_changed<T>(&mut self, mutable: Entity)
     where
         T: Send + Sync + 'static,
     {
-        let value = self.get_mut::<MutableCell<T>>(mutable).unwrap();
-        let inner = value.map_unchanged(|v| &mut v.0);
-        (updater)(inner);
+        self.trigger_targets(MutableChanged::<T>::new(mutable), mutable);
+    }
+
+    fn begin_history_group(&mut self) {
+        if let Some(mut history) = self.get_resource_mut::<MutableHistory>() {
+            history.begin_group();
+        }
+    }
+
+    fn end_history_group(&mut self) {
+        if let Some(mut history) = self.get_resource_mut::<MutableHistory>() {
+            history.end_group();
+        }
     }
 }
 
@@ -231,69 +893,248 @@ impl CreateMutable for World {
     where
         T: Send + Sync + 'static,
     {
-        let cell = self.spawn(MutableCell::<T>(init)).id();
+        let cell = self.spawn(MutableCell::<T>::new(init)).id();
         Mutable {
             cell,
             marker: PhantomData,
         }
     }
-}
 
-impl<'w> ReadMutable for DeferredWorld<'w> {
-    fn read_mutable<T>(&self, mutable: &Mutable<T>) -> T
+    fn create_mutable_with_eq<T>(&mut self, init: T, eq: fn(&T, &T) -> bool) -> Mutable<T>
     where
-        T: Send + Sync + Copy + 'static,
+        T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        mutable_entity.get::<MutableCell<T>>().unwrap().0
+        let cell = self.spawn(MutableCell::with_eq(init, eq)).id();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
     }
+}
 
-    fn read_mutable_clone<T>(&self, mutable: &Mutable<T>) -> T
+impl<'w> ReadMutable for DeferredWorld<'w> {
+    fn try_read_mutable_map<T, U, F: Fn(&T) -> U>(
+        &self,
+        mutable: &Mutable<T>,
+        f: F,
+    ) -> Result<U, MutableError>
     where
-        T: Send + Sync + Clone + 'static,
+        T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        mutable_entity.get::<MutableCell<T>>().unwrap().0.clone()
+        get_cell(self, mutable.cell).map(|cell| f(&cell.0))
     }
 
-    fn read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> &T
+    fn try_read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> Result<&T, MutableError>
     where
         T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        &mutable_entity.get::<MutableCell<T>>().unwrap().0
+        get_cell(self, mutable.cell).map(|cell| &cell.0)
     }
 
-    fn read_mutable_map<T, U, F: Fn(&T) -> U>(&self, mutable: &Mutable<T>, f: F) -> U
+    fn try_read_mutable_version<T>(&self, mutable: &Mutable<T>) -> Result<u64, MutableError>
     where
         T: Send + Sync + 'static,
     {
-        let mutable_entity = self.entity(mutable.cell);
-        f(&mutable_entity.get::<MutableCell<T>>().unwrap().0)
+        get_cell::<T>(self, mutable.cell).map(|cell| cell.1.version)
+    }
+
+    fn try_read_world<U>(&self, f: fn(&World) -> U) -> Result<U, MutableError> {
+        Ok(f(self))
     }
 }
 
 impl<'w> WriteMutable for DeferredWorld<'w> {
-    /// Write the value of a mutable variable. Does nothing if the value being set matches the
-    /// existing value.
-    fn write_mutable<T>(&mut self, mutable: Entity, value: T)
+    fn try_update_mutable_cell_silently<T, F: FnOnce(Mut<T>, Option<fn(&T, &T) -> bool>)>(
+        &mut self,
+        mutable: Entity,
+        updater: F,
+    ) -> Result<bool, MutableError>
     where
-        T: Send + Sync + PartialEq + 'static,
+        T: Send + Sync + 'static,
     {
-        let mut entt = self.entity_mut(mutable);
-        let mut cell = entt.get_mut::<MutableCell<T>>().unwrap();
-        if cell.0 != value {
-            cell.0 = value;
+        let tracked = self
+            .get::<TrackedMutable>(mutable)
+            .copied()
+            .filter(|_| self.contains_resource::<MutableHistory>());
+        let Some(mut cell) = self.get_mut::<MutableCell<T>>(mutable) else {
+            return Err(MutableError::missing(self, mutable));
+        };
+        let eq = cell.1.eq;
+        let entry = tracked.map(|tracked| tracked.snapshot(mutable, &cell.0));
+        let value = cell.reborrow().map_unchanged(|v| &mut v.0);
+        if !run_updater(value, |value| updater(value, eq)) {
+            return Ok(false);
         }
+        cell.bypass_change_detection().1.version += 1;
+        if let Some(entry) = entry {
+            self.resource_mut::<MutableHistory>().record(entry);
+        }
+        Ok(true)
     }
 
-    fn update_mutable<T, F: FnOnce(Mut<T>)>(&mut self, mutable: Entity, updater: F)
+    fn trigger_mutable_changed<T>(&mut self, mutable: Entity)
     where
         T: Send + Sync + 'static,
     {
-        let value = self.get_mut::<MutableCell<T>>(mutable).unwrap();
-        let inner = value.map_unchanged(|v| &mut v.0);
-        (updater)(inner);
+        self.trigger_targets(MutableChanged::<T>::new(mutable), mutable);
+    }
+
+    fn begin_history_group(&mut self) {
+        if let Some(mut history) = self.get_resource_mut::<MutableHistory>() {
+            history.begin_group();
+        }
+    }
+
+    fn end_history_group(&mut self) {
+        if let Some(mut history) = self.get_resource_mut::<MutableHistory>() {
+            history.end_group();
+        }
+    }
+}
+
+/// System parameter which gives access to mutables of type `T`, so that ordinary systems can
+/// read and write them without exclusive world access. Systems which use [`Mutables`] of
+/// different types can run in parallel. Changes to mutables which track history are recorded in
+/// the [`MutableHistory`] when the system's commands are applied.
+///
+/// Only mutables of type `T` can be accessed; other types will fail with
+/// [`MutableError::WrongType`]. Resource signals cannot be read through this parameter: reading
+/// them returns [`MutableError::NoWorld`], so use [`crate::Signal::try_get`] when the signal may
+/// be a resource signal.
+#[derive(SystemParam)]
+pub struct Mutables<'w, 's, T: Send + Sync + 'static> {
+    cells: Query<'w, 's, &'static mut MutableCell<T>>,
+    tracked: Query<'w, 's, &'static TrackedMutable>,
+    commands: Commands<'w, 's>,
+}
+
+impl<T: Send + Sync + 'static> Mutables<'_, '_, T> {
+    /// Convert a query error into a [`MutableError`].
+    fn error(err: QueryEntityError, mutable: Entity) -> MutableError {
+        match err {
+            QueryEntityError::NoSuchEntity(_) => MutableError::NoEntity(mutable),
+            _ => MutableError::WrongType(mutable),
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> ReadMutable for Mutables<'_, '_, T> {
+    fn try_read_mutable_map<U, R, F: Fn(&U) -> R>(
+        &self,
+        mutable: &Mutable<U>,
+        f: F,
+    ) -> Result<R, MutableError>
+    where
+        U: Send + Sync + 'static,
+    {
+        self.try_read_mutable_as_ref(mutable).map(f)
+    }
+
+    fn try_read_mutable_as_ref<U>(&self, mutable: &Mutable<U>) -> Result<&U, MutableError>
+    where
+        U: Send + Sync + 'static,
+    {
+        let cell = self
+            .cells
+            .get(mutable.cell)
+            .map_err(|err| Self::error(err, mutable.cell))?;
+        (&cell.0 as &dyn Any)
+            .downcast_ref::<U>()
+            .ok_or(MutableError::WrongType(mutable.cell))
+    }
+
+    fn try_read_mutable_version<U>(&self, mutable: &Mutable<U>) -> Result<u64, MutableError>
+    where
+        U: Send + Sync + 'static,
+    {
+        if TypeId::of::<U>() != TypeId::of::<T>() {
+            return Err(MutableError::WrongType(mutable.cell));
+        }
+        self.cells
+            .get(mutable.cell)
+            .map(|cell| cell.1.version)
+            .map_err(|err| Self::error(err, mutable.cell))
+    }
+}
+
+impl<T: Send + Sync + 'static> WriteMutable for Mutables<'_, '_, T> {
+    fn try_update_mutable_cell_silently<U, F: FnOnce(Mut<U>, Option<fn(&U, &U) -> bool>)>(
+        &mut self,
+        mutable: Entity,
+        updater: F,
+    ) -> Result<bool, MutableError>
+    where
+        U: Send + Sync + 'static,
+    {
+        if TypeId::of::<U>() != TypeId::of::<T>() {
+            return Err(MutableError::WrongType(mutable));
+        }
+        let mut cell = self
+            .cells
+            .get_mut(mutable)
+            .map_err(|err| Self::error(err, mutable))?;
+        let eq = (&cell.1.eq as &dyn Any)
+            .downcast_ref::<Option<fn(&U, &U) -> bool>>()
+            .copied()
+            .flatten();
+        let inner = cell
+            .reborrow()
+            .map_unchanged(|v| (&mut v.0 as &mut dyn Any).downcast_mut::<U>().unwrap());
+        let entry = self
+            .tracked
+            .get(mutable)
+            .ok()
+            .map(|tracked| tracked.snapshot(mutable, &*inner));
+        if !run_updater(inner, |value| updater(value, eq)) {
+            return Ok(false);
+        }
+        cell.bypass_change_detection().1.version += 1;
+        if let Some(entry) = entry {
+            // The history is recorded by a command, so that systems which write mutables don't
+            // need access to the history resource.
+            self.commands.queue(RecordHistoryCommand(entry));
+        }
+        Ok(true)
+    }
+
+    fn trigger_mutable_changed<U>(&mut self, mutable: Entity)
+    where
+        U: Send + Sync + 'static,
+    {
+        self.commands
+            .trigger_targets(MutableChanged::<U>::new(mutable), mutable);
+    }
+
+    fn begin_history_group(&mut self) {
+        // History is recorded by commands, so the group must be too.
+        self.commands
+            .queue(|world: &mut World| world.begin_history_group());
+    }
+
+    fn end_history_group(&mut self) {
+        self.commands
+            .queue(|world: &mut World| world.end_history_group());
+    }
+}
+
+/// Debug check which reports mutables whose owner has been despawned without despawning its
+/// children. Any handles to such a mutable were held by effects belonging to the owner, so the
+/// cell can no longer be reached, and will never be freed.
+#[cfg(debug_assertions)]
+pub(crate) fn report_orphaned_mutables(
+    cells: Query<(Entity, &Parent), With<AnyMutableCell>>,
+    entities: &bevy::ecs::entity::Entities,
+    mut reported: Local<bevy::utils::HashSet<Entity>>,
+) {
+    reported.retain(|cell| entities.contains(*cell));
+    for (cell, parent) in cells.iter() {
+        if !entities.contains(parent.get()) && reported.insert(cell) {
+            warn!(
+                "Mutable {} is orphaned: its owner {} was despawned",
+                cell,
+                parent.get()
+            );
+        }
     }
 }
 
@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+
+use crate::mutable::{Mutable, ReadMutable, WriteMutable};
+
+/// Maximum number of operations kept in the change log. Readers which fall further behind than
+/// this will see a gap in the log, and must rebuild from the current contents.
+const MAX_LOG_LEN: usize = 1024;
+
+/// A structural change to a [`MutableVec`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum VecOp {
+    /// An item was inserted at the given index.
+    Insert(usize),
+    /// The item at the given index was removed.
+    Remove(usize),
+    /// The item at the given index was replaced.
+    Replace(usize),
+    /// The items at the given indices were swapped.
+    Swap(usize, usize),
+    /// An item was moved from the first index to the second.
+    Move(usize, usize),
+    /// All items were removed.
+    Clear,
+}
+
+/// The contents of a [`MutableVec`], along with a log of recent operations.
+pub(crate) struct VecState<T> {
+    pub(crate) items: Vec<T>,
+    /// Sequence number of the first entry in `log`.
+    pub(crate) base: usize,
+    pub(crate) log: Vec<VecOp>,
+}
+
+impl<T> VecState<T> {
+    /// The sequence number following the most recent operation.
+    pub(crate) fn seq(&self) -> usize {
+        self.base + self.log.len()
+    }
+
+    /// Returns the operations since the given sequence number, or `None` if they are no longer
+    /// in the log.
+    pub(crate) fn ops_since(&self, seq: usize) -> Option<&[VecOp]> {
+        if seq < self.base {
+            return None;
+        }
+        self.log.get(seq - self.base..)
+    }
+
+    fn record(&mut self, op: VecOp) {
+        if self.log.len() >= MAX_LOG_LEN {
+            let trim = MAX_LOG_LEN / 2;
+            self.log.drain(..trim);
+            self.base += trim;
+        }
+        self.log.push(op);
+    }
+}
+
+/// Contains a reference to a reactive vector. Unlike a `Mutable<Vec<T>>`, a [`MutableVec`]
+/// records each operation performed on it, so that list views can update their children
+/// without diffing the whole list.
+pub struct MutableVec<T> {
+    pub(crate) inner: Mutable<VecState<T>>,
+}
+
+impl<T> MutableVec<T> {
+    /// The entity that holds the vector.
+    pub fn id(&self) -> Entity {
+        self.inner.id()
+    }
+}
+
+impl<T> PartialEq for MutableVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl<T> std::fmt::Debug for MutableVec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MutableVec").field(&self.id()).finish()
+    }
+}
+
+impl<T> Copy for MutableVec<T> {}
+impl<T> Clone for MutableVec<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> MutableVec<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub(crate) fn new(inner: Mutable<VecState<T>>) -> Self {
+        Self { inner }
+    }
+
+    pub(crate) fn initial_state(items: Vec<T>) -> VecState<T> {
+        VecState {
+            items,
+            base: 0,
+            log: Vec::new(),
+        }
+    }
+
+    /// Call a function with a slice containing the items of the vector.
+    pub fn with<R: ReadMutable, U, F: Fn(&[T]) -> U>(&self, cx: &R, f: F) -> U {
+        cx.read_mutable_map(&self.inner, |state| f(&state.items))
+    }
+
+    /// Returns the number of items in the vector.
+    pub fn len<R: ReadMutable>(&self, cx: &R) -> usize {
+        self.with(cx, |items| items.len())
+    }
+
+    /// Returns true if the vector is empty.
+    pub fn is_empty<R: ReadMutable>(&self, cx: &R) -> bool {
+        self.with(cx, |items| items.is_empty())
+    }
+
+    /// Append an item to the end of the vector.
+    pub fn push<W: WriteMutable>(&self, w: &mut W, value: T) {
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            let index = state.items.len();
+            state.items.push(value);
+            state.record(VecOp::Insert(index));
+        });
+    }
+
+    /// Insert an item at the given index.
+    pub fn insert<W: WriteMutable>(&self, w: &mut W, index: usize, value: T) {
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            state.items.insert(index, value);
+            state.record(VecOp::Insert(index));
+        });
+    }
+
+    /// Remove and return the item at the given index.
+    pub fn remove<W: WriteMutable>(&self, w: &mut W, index: usize) -> T {
+        let mut removed = None;
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            removed = Some(state.items.remove(index));
+            state.record(VecOp::Remove(index));
+        });
+        removed.unwrap()
+    }
+
+    /// Remove and return the last item, if any.
+    pub fn pop<W: WriteMutable>(&self, w: &mut W) -> Option<T> {
+        let mut removed = None;
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            // Check through a shared borrow first, so that popping an empty vector is not
+            // counted as a change.
+            if state.items.is_empty() {
+                return;
+            }
+            removed = state.items.pop();
+            let index = state.items.len();
+            state.record(VecOp::Remove(index));
+        });
+        removed
+    }
+
+    /// Replace the item at the given index.
+    pub fn set<W: WriteMutable>(&self, w: &mut W, index: usize, value: T) {
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            state.items[index] = value;
+            state.record(VecOp::Replace(index));
+        });
+    }
+
+    /// Swap the items at the given indices.
+    pub fn swap<W: WriteMutable>(&self, w: &mut W, a: usize, b: usize) {
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            state.items.swap(a, b);
+            state.record(VecOp::Swap(a, b));
+        });
+    }
+
+    /// Move the item at index `from` so that it ends up at index `to`.
+    pub fn move_item<W: WriteMutable>(&self, w: &mut W, from: usize, to: usize) {
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            let item = state.items.remove(from);
+            state.items.insert(to, item);
+            state.record(VecOp::Move(from, to));
+        });
+    }
+
+    /// Remove all items.
+    pub fn clear<W: WriteMutable>(&self, w: &mut W) {
+        w.update_mutable(self.id(), |mut state: Mut<VecState<T>>| {
+            state.items.clear();
+            state.record(VecOp::Clear);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CreateMutable;
+
+    #[test]
+    fn test_ops_since() {
+        let mut state = MutableVec::initial_state(vec![1, 2]);
+        state.record(VecOp::Insert(2));
+        state.record(VecOp::Swap(0, 1));
+        assert_eq!(state.seq(), 2);
+        assert_eq!(
+            state.ops_since(0),
+            Some(&[VecOp::Insert(2), VecOp::Swap(0, 1)][..])
+        );
+        assert_eq!(state.ops_since(2), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_log_trim() {
+        let mut state = MutableVec::<i32>::initial_state(vec![]);
+        for _ in 0..MAX_LOG_LEN + 1 {
+            state.record(VecOp::Clear);
+        }
+        assert_eq!(state.seq(), MAX_LOG_LEN + 1);
+        assert_eq!(state.ops_since(0), None);
+        assert_eq!(state.ops_since(MAX_LOG_LEN).map(|ops| ops.len()), Some(1));
+    }
+
+    #[test]
+    fn test_pop_empty() {
+        let mut world = World::new();
+        let vec = world.create_mutable_vec(vec![1]);
+        assert_eq!(vec.pop(&mut world), Some(1));
+        let version = vec.inner.version(&world);
+        assert_eq!(vec.pop(&mut world), None);
+        assert_eq!(vec.inner.version(&world), version);
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use ron::Value;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::mutable::{Mutable, MutableCell, MutableChanged};
+
+/// Plugin which saves persistent mutables to disk. See [`Mutable::persist`].
+pub struct PersistPlugin {
+    /// Directory where the files are stored. Each mutable is saved as `<key>.ron`.
+    pub directory: PathBuf,
+
+    /// How long to wait after the last change before writing a file.
+    pub debounce: Duration,
+}
+
+impl Default for PersistPlugin {
+    fn default() -> Self {
+        let settings = PersistSettings::default();
+        Self {
+            directory: settings.directory,
+            debounce: settings.debounce,
+        }
+    }
+}
+
+impl Plugin for PersistPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PersistSettings {
+            directory: self.directory.clone(),
+            debounce: self.debounce,
+        });
+        app.add_systems(Last, save_persistent_mutables);
+        // Save any pending change when a persistent mutable is despawned, since the debounced
+        // save would no longer find it.
+        app.world_mut()
+            .register_component_hooks::<PersistentMutable>()
+            .on_remove(|world, entity, _cond| {
+                let persist = world.get::<PersistentMutable>(entity).unwrap();
+                if persist.changed.is_some() {
+                    save(&world, &settings(&world), entity, persist);
+                }
+            });
+    }
+}
+
+/// Where and when persistent mutables are saved.
+#[derive(Resource, Clone)]
+pub struct PersistSettings {
+    /// Directory where the files are stored.
+    pub directory: PathBuf,
+
+    /// How long to wait after the last change before writing a file.
+    pub debounce: Duration,
+}
+
+impl Default for PersistSettings {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("settings"),
+            debounce: Duration::from_secs(1),
+        }
+    }
+}
+
+impl PersistSettings {
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{key}.ron"))
+    }
+}
+
+/// Marks a mutable which is saved to disk.
+#[derive(Component)]
+pub(crate) struct PersistentMutable {
+    key: String,
+    serialize: fn(&World, Entity) -> Option<String>,
+    /// When the value last changed, if it has not been saved since.
+    changed: Option<Instant>,
+}
+
+impl<T> Mutable<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    /// Save this mutable to disk whenever it changes, and restore the saved value now if there
+    /// is one. The file is named after `key`, which must be unique, and is stored in the directory
+    /// given by [`PersistPlugin`]. Restoring the saved value does not trigger [`MutableChanged`]
+    /// or record an undo step, and pending changes are saved when the mutable is despawned.
+    ///
+    /// If fields have been added to `T` since the value was saved, they keep their current
+    /// values, as if `T` used `#[serde(default)]`. If the saved value still cannot be read, the
+    /// current value is kept and the file is moved to `<key>.ron.bak`, so that the next save
+    /// doesn't overwrite it.
+    pub fn persist(&self, commands: &mut Commands, key: impl Into<String>) {
+        commands.queue(PersistCommand::<T> {
+            cell: self.cell,
+            key: key.into(),
+            marker: std::marker::PhantomData,
+        });
+    }
+}
+
+/// Command which loads the saved value of a mutable, and starts tracking changes.
+struct PersistCommand<T> {
+    cell: Entity,
+    key: String,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<T> Command for PersistCommand<T>
+where
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn apply(self, world: &mut World) {
+        let settings = settings(world);
+        let Some(mut cell) = world.get_mut::<MutableCell<T>>(self.cell) else {
+            return;
+        };
+        // Restoring the saved value is part of creating the mutable rather than a change, so it
+        // doesn't trigger `MutableChanged` or add an undo step.
+        if let Some(value) = load(&settings.path(&self.key), &cell.0) {
+            cell.0 = value;
+            cell.1.version += 1;
+        }
+        world
+            .entity_mut(self.cell)
+            .insert(PersistentMutable {
+                key: self.key,
+                serialize: serialize::<T>,
+                changed: None,
+            })
+            .observe(
+                |trigger: Trigger<MutableChanged<T>>, mut cells: Query<&mut PersistentMutable>| {
+                    if let Ok(mut persist) = cells.get_mut(trigger.entity()) {
+                        persist.changed = Some(Instant::now());
+                    }
+                },
+            );
+    }
+}
+
+fn settings(world: &World) -> PersistSettings {
+    world
+        .get_resource::<PersistSettings>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Read a saved value, filling in any missing fields from `current`. If the file can't be read,
+/// it is moved aside so that it isn't lost when the value is next saved.
+fn load<T: Serialize + DeserializeOwned>(path: &Path, current: &T) -> Option<T> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let err = match ron::from_str(&text) {
+        Ok(value) => return Some(value),
+        Err(err) => err,
+    };
+    if let Some(value) = merge(&text, current) {
+        return Some(value);
+    }
+    let backup = path.with_extension("ron.bak");
+    warn!(
+        "Ignoring saved value in {}: {}. Moving it to {}",
+        path.display(),
+        err,
+        backup.display()
+    );
+    if let Err(err) = std::fs::rename(path, &backup) {
+        warn!("Failed to move {}: {}", path.display(), err);
+    }
+    None
+}
+
+/// Deserialize `text`, taking any fields which it is missing from `current`. This goes through
+/// [`Value`], which can't represent enums, so it only succeeds for types without them.
+fn merge<T: Serialize + DeserializeOwned>(text: &str, current: &T) -> Option<T> {
+    let saved: Value = ron::from_str(text).ok()?;
+    let current: Value = ron::from_str(&ron::to_string(current).ok()?).ok()?;
+    merge_values(saved, current).into_rust().ok()
+}
+
+fn merge_values(saved: Value, current: Value) -> Value {
+    match (saved, current) {
+        (Value::Map(mut saved), Value::Map(current)) => {
+            for (key, value) in current {
+                let merged = match saved.remove(&key) {
+                    Some(saved) => merge_values(saved, value),
+                    None => value,
+                };
+                saved.insert(key, merged);
+            }
+            Value::Map(saved)
+        }
+        (saved, _) => saved,
+    }
+}
+
+fn serialize<T: Serialize + Send + Sync + 'static>(world: &World, cell: Entity) -> Option<String> {
+    let value = &world.get::<MutableCell<T>>(cell)?.0;
+    ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).ok()
+}
+
+/// Write the files for mutables which have changed, once the debounce interval has passed, or
+/// immediately if the app is exiting.
+fn save_persistent_mutables(world: &mut World) {
+    let settings = settings(world);
+    let exiting = world
+        .get_resource::<Events<AppExit>>()
+        .is_some_and(|events| !events.is_empty());
+    let now = Instant::now();
+    let mut query = world.query::<(Entity, &PersistentMutable)>();
+    let due: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, persist)| {
+            persist
+                .changed
+                .is_some_and(|changed| exiting || now - changed >= settings.debounce)
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+    for entity in due {
+        let persist = world.get::<PersistentMutable>(entity).unwrap();
+        save(world, &settings, entity, persist);
+        world.get_mut::<PersistentMutable>(entity).unwrap().changed = None;
+    }
+}
+
+/// Write the file for a persistent mutable.
+fn save(world: &World, settings: &PersistSettings, entity: Entity, persist: &PersistentMutable) {
+    let path = settings.path(&persist.key);
+    if let Some(text) = (persist.serialize)(world, entity) {
+        let result =
+            std::fs::create_dir_all(&settings.directory).and_then(|_| std::fs::write(&path, text));
+        if let Err(err) = result {
+            warn!("Failed to save {}: {}", path.display(), err);
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::ops::Range;
+
+use bevy::prelude::*;
+
+use crate::lcs::lcs;
+
+/// Callbacks used by [`reconcile`] to build, patch and destroy the views for a list of items.
+pub(crate) trait Reconciler<Item> {
+    /// The view state for a single item.
+    type State;
+
+    /// Returns true if the view for `state` can be reused for `item`.
+    fn matches(&self, state: &Self::State, item: &Item) -> bool;
+
+    /// Build the view for a new item.
+    fn build(&mut self, world: &mut World, item: &Item) -> Self::State;
+
+    /// Patch the view for an item which matched an item from the previous update.
+    fn retain(&mut self, world: &mut World, state: Self::State, item: &Item) -> Self::State;
+
+    /// Destroy the view for an item which is no longer present.
+    fn raze(&mut self, world: &mut World, state: Self::State);
+}
+
+/// Match the view states from the previous update with the updated list of items. Matching
+/// items are patched, other items are inserted or deleted. Returns the new view states, in the
+/// same order as `next_items`.
+pub(crate) fn reconcile<Item, R: Reconciler<Item>>(
+    reconciler: &mut R,
+    world: &mut World,
+    prev_state: Vec<R::State>,
+    next_items: &[Item],
+) -> Vec<R::State> {
+    let mut prev_state: Vec<Option<R::State>> = prev_state.into_iter().map(Some).collect();
+    let mut out = Vec::with_capacity(next_items.len());
+    let prev_len = prev_state.len();
+    build_recursive(
+        reconciler,
+        world,
+        &mut prev_state,
+        0..prev_len,
+        next_items,
+        0..next_items.len(),
+        &mut out,
+    );
+    out
+}
+
+/// Uses the sequence of key values to match the previous array items with the updated
+/// array items. Matching items are patched, other items are inserted or deleted.
+///
+/// # Arguments
+///
+/// * `reconciler` - [`Reconciler`] used to build individual elements.
+/// * `prev_state` - Array of view state elements from previous update.
+/// * `prev_range` - The range of elements we are comparing in `prev_state`.
+/// * `next_items` - Array of items to be built.
+/// * `next_range` - The range of elements we are comparing in `next_items`.
+/// * `out` - The resulting view state elements.
+#[allow(clippy::too_many_arguments, clippy::needless_range_loop)]
+fn build_recursive<Item, R: Reconciler<Item>>(
+    reconciler: &mut R,
+    world: &mut World,
+    prev_state: &mut [Option<R::State>],
+    prev_range: Range<usize>,
+    next_items: &[Item],
+    next_range: Range<usize>,
+    out: &mut Vec<R::State>,
+) {
+    // Look for longest common subsequence.
+    // prev_start and next_start are *relative to the slice*.
+    let (prev_start, next_start, lcs_length) = lcs(
+        &prev_state[prev_range.clone()],
+        &next_items[next_range.clone()],
+        |a, b| a.as_ref().is_some_and(|a| reconciler.matches(a, b)),
+    );
+
+    // If there was nothing in common
+    if lcs_length == 0 {
+        // Raze old elements
+        for i in prev_range {
+            reconciler.raze(world, prev_state[i].take().unwrap());
+        }
+        // Build new elements
+        for i in next_range {
+            out.push(reconciler.build(world, &next_items[i]));
+        }
+        return;
+    }
+
+    // Adjust prev_start and next_start to be relative to the entire state array.
+    let prev_start = prev_start + prev_range.start;
+    let next_start = next_start + next_range.start;
+
+    // Stuff that precedes the LCS.
+    if prev_start > prev_range.start {
+        if next_start > next_range.start {
+            // Both prev and next have entries before lcs, so recurse
+            build_recursive(
+                reconciler,
+                world,
+                prev_state,
+                prev_range.start..prev_start,
+                next_items,
+                next_range.start..next_start,
+                out,
+            )
+        } else {
+            // Deletions
+            for i in prev_range.start..prev_start {
+                reconciler.raze(world, prev_state[i].take().unwrap());
+            }
+        }
+    } else if next_start > next_range.start {
+        // Insertions
+        for i in next_range.start..next_start {
+            out.push(reconciler.build(world, &next_items[i]));
+        }
+    }
+
+    // For items that match, keep the view but patch the value.
+    for i in 0..lcs_length {
+        let prev = prev_state[prev_start + i].take().unwrap();
+        out.push(reconciler.retain(world, prev, &next_items[next_start + i]));
+    }
+
+    // Stuff that follows the LCS.
+    let prev_end = prev_start + lcs_length;
+    let next_end = next_start + lcs_length;
+    if prev_end < prev_range.end {
+        if next_end < next_range.end {
+            // Both prev and next have entries after lcs, so recurse
+            build_recursive(
+                reconciler,
+                world,
+                prev_state,
+                prev_end..prev_range.end,
+                next_items,
+                next_end..next_range.end,
+                out,
+            );
+        } else {
+            // Deletions
+            for i in prev_end..prev_range.end {
+                reconciler.raze(world, prev_state[i].take().unwrap());
+            }
+        }
+    } else if next_end < next_range.end {
+        // Insertions
+        for i in next_end..next_range.end {
+            out.push(reconciler.build(world, &next_items[i]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{reconcile, Reconciler};
+
+    /// Reconciler which records each call. The state is the item it was built for.
+    #[derive(Default)]
+    struct MockReconciler {
+        built: Vec<i32>,
+        retained: Vec<i32>,
+        razed: Vec<i32>,
+    }
+
+    impl Reconciler<i32> for MockReconciler {
+        type State = i32;
+
+        fn matches(&self, state: &i32, item: &i32) -> bool {
+            state == item
+        }
+
+        fn build(&mut self, _world: &mut World, item: &i32) -> i32 {
+            self.built.push(*item);
+            *item
+        }
+
+        fn retain(&mut self, _world: &mut World, state: i32, _item: &i32) -> i32 {
+            self.retained.push(state);
+            state
+        }
+
+        fn raze(&mut self, _world: &mut World, state: i32) {
+            self.razed.push(state);
+        }
+    }
+
+    fn run(prev: Vec<i32>, next: &[i32]) -> (Vec<i32>, MockReconciler) {
+        let mut world = World::new();
+        let mut reconciler = MockReconciler::default();
+        let out = reconcile(&mut reconciler, &mut world, prev, next);
+        (out, reconciler)
+    }
+
+    #[test]
+    fn test_empty() {
+        let (out, r) = run(vec![], &[]);
+        assert!(out.is_empty());
+        assert!(r.built.is_empty());
+        assert!(r.retained.is_empty());
+        assert!(r.razed.is_empty());
+    }
+
+    #[test]
+    fn test_build_all() {
+        let (out, r) = run(vec![], &[1, 2, 3]);
+        assert_eq!(out, vec![1, 2, 3]);
+        assert_eq!(r.built, vec![1, 2, 3]);
+        assert!(r.retained.is_empty());
+        assert!(r.razed.is_empty());
+    }
+
+    #[test]
+    fn test_retain_all() {
+        let (out, r) = run(vec![1, 2, 3], &[1, 2, 3]);
+        assert_eq!(out, vec![1, 2, 3]);
+        assert!(r.built.is_empty());
+        assert_eq!(r.retained, vec![1, 2, 3]);
+        assert!(r.razed.is_empty());
+    }
+
+    #[test]
+    fn test_insert() {
+        let (out, r) = run(vec![1, 3], &[0, 1, 2, 3, 4]);
+        assert_eq!(out, vec![0, 1, 2, 3, 4]);
+        assert_eq!(r.built, vec![0, 2, 4]);
+        assert_eq!(r.retained, vec![1, 3]);
+        assert!(r.razed.is_empty());
+    }
+
+    #[test]
+    fn test_remove() {
+        let (out, r) = run(vec![0, 1, 2, 3, 4], &[1, 3]);
+        assert_eq!(out, vec![1, 3]);
+        assert!(r.built.is_empty());
+        assert_eq!(r.retained, vec![1, 3]);
+        assert_eq!(r.razed, vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn test_replace_all() {
+        let (out, r) = run(vec![1, 2], &[3, 4]);
+        assert_eq!(out, vec![3, 4]);
+        assert_eq!(r.built, vec![3, 4]);
+        assert!(r.retained.is_empty());
+        assert_eq!(r.razed, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_mixed() {
+        let (out, r) = run(vec![1, 2, 3, 4, 5], &[1, 6, 3, 4, 7]);
+        assert_eq!(out, vec![1, 6, 3, 4, 7]);
+        assert_eq!(r.built, vec![6, 7]);
+        assert_eq!(r.retained, vec![1, 3, 4]);
+        assert_eq!(r.razed, vec![2, 5]);
+    }
+
+    #[test]
+    fn test_move() {
+        // Moving an item razes it and builds it again at its new position.
+        let (out, r) = run(vec![1, 2, 3], &[3, 1, 2]);
+        assert_eq!(out, vec![3, 1, 2]);
+        assert_eq!(r.built, vec![3]);
+        assert_eq!(r.retained, vec![1, 2]);
+        assert_eq!(r.razed, vec![3]);
+    }
+}
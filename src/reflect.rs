@@ -0,0 +1,282 @@
+use std::any::TypeId;
+
+use bevy::{
+    ecs::{
+        reflect::ReflectComponentFns,
+        world::{EntityMut, EntityWorldMut},
+    },
+    prelude::*,
+    reflect::{FromType, GetTypeRegistration, ReflectRef, TypeRegistry, Typed},
+};
+
+use crate::{
+    history::{RecordHistoryCommand, TrackedMutable},
+    mutable::{MutableCell, MutableChanged, MutableError, WriteMutable},
+};
+
+/// Type data for a reflected mutable, which allows tools such as inspectors to read and edit
+/// mutables without knowing their type. Look this up in the type registry using the type of the
+/// mutable's cell component.
+///
+/// Editing a value with [`ReflectMutable::apply`] notifies observers and records history in the
+/// same way as [`crate::Mutable::set`]. The cell component is also registered with
+/// `ReflectComponent`, for tools such as scenes and generic inspectors. Its `insert` and
+/// `apply_or_insert` behave like [`ReflectMutable::apply`] when the mutable already exists.
+/// `ReflectComponent::apply` can't access the world, so observers are notified and history is
+/// recorded in [`PreUpdate`] instead. Writes through `ReflectComponent::reflect_mut` bypass
+/// notification entirely.
+#[derive(Clone)]
+pub struct ReflectMutable {
+    reflect: fn(&World, Entity) -> Result<&dyn PartialReflect, MutableError>,
+    apply: fn(&mut World, Entity, &dyn PartialReflect) -> Result<(), MutableError>,
+}
+
+impl ReflectMutable {
+    /// Get the value of the mutable held by `entity`.
+    pub fn reflect<'w>(
+        &self,
+        world: &'w World,
+        entity: Entity,
+    ) -> Result<&'w dyn PartialReflect, MutableError> {
+        (self.reflect)(world, entity)
+    }
+
+    /// Apply a new value to the mutable held by `entity`. Does nothing if the value is unchanged.
+    /// Returns [`MutableError::WrongType`] if the value cannot be applied to the mutable.
+    pub fn apply(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        value: &dyn PartialReflect,
+    ) -> Result<(), MutableError> {
+        (self.apply)(world, entity, value)
+    }
+}
+
+impl<T: Reflect + Send + Sync + 'static> FromType<MutableCell<T>> for ReflectMutable {
+    fn from_type() -> Self {
+        Self {
+            reflect: |world, entity| {
+                world
+                    .get::<MutableCell<T>>(entity)
+                    .map(|cell| cell.0.as_partial_reflect())
+                    .ok_or_else(|| MutableError::missing(world, entity))
+            },
+            apply: apply_value::<T>,
+        }
+    }
+}
+
+/// Apply a reflected value to a mutable, notifying observers and recording history.
+fn apply_value<T: Reflect + Send + Sync + 'static>(
+    world: &mut World,
+    entity: Entity,
+    value: &dyn PartialReflect,
+) -> Result<(), MutableError> {
+    let mut result = Ok(());
+    world.try_update_mutable(entity, |mut cell: Mut<T>| {
+        if cell.reflect_partial_eq(value) != Some(true) {
+            result = cell
+                .try_apply(value)
+                .map_err(|_| MutableError::WrongType(entity));
+        }
+    })?;
+    result
+}
+
+/// Returns the value held by a reflected [`MutableCell`].
+fn cell_value(cell: &dyn PartialReflect) -> Option<&dyn PartialReflect> {
+    match cell.reflect_ref() {
+        ReflectRef::TupleStruct(cell) => cell.field(0),
+        _ => None,
+    }
+}
+
+/// `ReflectComponent` data for a [`MutableCell`], which writes existing mutables with
+/// [`WriteMutable::try_update_mutable`] rather than replacing the component.
+fn reflect_component<T>() -> ReflectComponent
+where
+    T: Reflect + FromReflect + Typed + GetTypeRegistration + Send + Sync + 'static,
+{
+    let mut fns = ReflectComponentFns::new::<MutableCell<T>>();
+    fns.insert = insert_cell::<T>;
+    fns.apply_or_insert = insert_cell::<T>;
+    fns.apply = apply_cell::<T>;
+    ReflectComponent::new(fns)
+}
+
+fn insert_cell<T>(entity: &mut EntityWorldMut, cell: &dyn PartialReflect, _: &TypeRegistry)
+where
+    T: Reflect + FromReflect + Send + Sync + 'static,
+{
+    let Some(value) = cell_value(cell) else {
+        warn!(
+            "Cannot insert {} as a mutable cell",
+            cell.reflect_type_path()
+        );
+        return;
+    };
+    if !entity.contains::<MutableCell<T>>() {
+        match T::from_reflect(value) {
+            Some(value) => {
+                entity.insert(MutableCell::new(value));
+            }
+            None => warn!(
+                "Cannot insert {} as a mutable cell",
+                cell.reflect_type_path()
+            ),
+        }
+        return;
+    }
+    let id = entity.id();
+    if let Err(err) = entity.world_scope(|world| apply_value::<T>(world, id, value)) {
+        warn!(
+            "Cannot apply {} to mutable: {err:?}",
+            cell.reflect_type_path()
+        );
+    }
+}
+
+fn apply_cell<T: Reflect + Send + Sync + 'static>(
+    mut entity: EntityMut,
+    cell: &dyn PartialReflect,
+) {
+    let Some(value) = cell_value(cell) else {
+        warn!("Cannot apply {} to mutable", cell.reflect_type_path());
+        return;
+    };
+    let id = entity.id();
+    let tracked = entity.get::<TrackedMutable>().copied();
+    let mut cell = entity.get_mut::<MutableCell<T>>().unwrap();
+    if cell.0.reflect_partial_eq(value) == Some(true) {
+        return;
+    }
+    let entry = tracked.map(|tracked| tracked.snapshot(id, &cell.0));
+    if cell.0.try_apply(value).is_err() {
+        warn!("Cannot apply value to mutable {id}");
+        return;
+    }
+    cell.1.version += 1;
+    // Keep the earliest entry, so that undo restores the value from before the first write.
+    let pending = cell.1.reflected.take().unwrap_or(entry);
+    cell.1.reflected = Some(pending);
+}
+
+/// Trigger [`MutableChanged`] and record history for mutables changed by
+/// `ReflectComponent::apply`.
+fn notify_reflected_mutables<T: Send + Sync + 'static>(
+    mut cells: Query<(Entity, &mut MutableCell<T>), Changed<MutableCell<T>>>,
+    mut commands: Commands,
+) {
+    for (entity, mut cell) in cells.iter_mut() {
+        let Some(entry) = cell.bypass_change_detection().1.reflected.take() else {
+            continue;
+        };
+        if let Some(entry) = entry {
+            commands.queue(RecordHistoryCommand(entry));
+        }
+        commands.trigger_targets(MutableChanged::<T>::new(entity), entity);
+    }
+}
+
+/// Extension trait for registering reflectable mutables.
+pub trait RegisterMutable {
+    /// Register [`Mutable`](crate::Mutable)s of type `T` with the type registry, so that they are
+    /// visible to reflection, along with [`ReflectMutable`] and `ReflectComponent` type data for
+    /// editing them.
+    fn register_mutable<T>(&mut self) -> &mut Self
+    where
+        T: Reflect + FromReflect + Typed + GetTypeRegistration + Send + Sync + 'static;
+}
+
+impl RegisterMutable for App {
+    fn register_mutable<T>(&mut self) -> &mut Self
+    where
+        T: Reflect + FromReflect + Typed + GetTypeRegistration + Send + Sync + 'static,
+    {
+        self.register_type::<MutableCell<T>>();
+        self.register_type_data::<MutableCell<T>, ReflectMutable>();
+        self.world()
+            .resource::<AppTypeRegistry>()
+            .write()
+            .get_mut(TypeId::of::<MutableCell<T>>())
+            .unwrap()
+            .insert(reflect_component::<T>());
+        self.add_systems(PreUpdate, notify_reflected_mutables::<T>);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CreateMutable, MutableHistory, UndoCommand};
+
+    #[derive(Resource, Default)]
+    struct Changes(u32);
+
+    fn setup() -> (App, Entity, ReflectComponent) {
+        let mut app = App::new();
+        app.register_mutable::<i32>();
+        app.init_resource::<Changes>();
+        app.insert_resource(MutableHistory::new(10));
+        let world = app.world_mut();
+        let mutable = world.create_mutable(0i32);
+        mutable.track_history(&mut world.commands());
+        world.flush();
+        world.entity_mut(mutable.id()).observe(
+            |_trigger: Trigger<MutableChanged<i32>>, mut changes: ResMut<Changes>| {
+                changes.0 += 1;
+            },
+        );
+        world.flush();
+        let reflect = app
+            .world()
+            .resource::<AppTypeRegistry>()
+            .read()
+            .get_type_data::<ReflectComponent>(TypeId::of::<MutableCell<i32>>())
+            .unwrap()
+            .clone();
+        (app, mutable.id(), reflect)
+    }
+
+    fn value(app: &App, cell: Entity) -> (i32, u64) {
+        let cell = app.world().get::<MutableCell<i32>>(cell).unwrap();
+        (cell.0, cell.1.version)
+    }
+
+    #[test]
+    fn test_reflect_component_insert() {
+        let (mut app, cell, reflect) = setup();
+        let registry = app.world().resource::<AppTypeRegistry>().clone();
+        reflect.apply_or_insert(
+            &mut app.world_mut().entity_mut(cell),
+            &MutableCell::new(5i32),
+            &registry.read(),
+        );
+        assert_eq!(value(&app, cell), (5, 1));
+        assert_eq!(app.world().resource::<Changes>().0, 1);
+
+        UndoCommand.apply(app.world_mut());
+        assert_eq!(value(&app, cell).0, 0);
+    }
+
+    #[test]
+    fn test_reflect_component_apply() {
+        let (mut app, cell, reflect) = setup();
+        reflect.apply(app.world_mut().entity_mut(cell), &MutableCell::new(5i32));
+        reflect.apply(app.world_mut().entity_mut(cell), &MutableCell::new(6i32));
+        assert_eq!(value(&app, cell), (6, 2));
+
+        app.update();
+        assert_eq!(app.world().resource::<Changes>().0, 1);
+        UndoCommand.apply(app.world_mut());
+        assert_eq!(value(&app, cell).0, 0);
+        assert_eq!(app.world().resource::<Changes>().0, 2);
+
+        // An equal value is not a change.
+        reflect.apply(app.world_mut().entity_mut(cell), &MutableCell::new(0i32));
+        app.update();
+        assert_eq!(app.world().resource::<Changes>().0, 2);
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    marker::PhantomData,
+    sync::{Arc, Mutex},
+};
+
+use bevy::{
+    ecs::{component::Tick, world::DeferredWorld},
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{
+    effect::{AnyEffect, EffectCell},
+    mutable::{MutableCell, MutableChanged, WriteMutable},
+    Mutable,
+};
+
+/// Trait for creating mutables which mirror a [`Resource`]. This lets widgets written against
+/// [`Mutable`] display, and edit, state which lives in resources.
+pub trait MirrorResource {
+    /// Create a [`Mutable`] which follows a resource. Whenever the resource changes, `map` is
+    /// called to compute the new value of the mutable. The resource must exist when the mutable
+    /// is created.
+    ///
+    /// Arguments:
+    /// * `map`: Computes the value of the mutable from the resource.
+    fn mirror_resource<R, T>(&mut self, map: fn(&R) -> T) -> Mutable<T>
+    where
+        R: Resource,
+        T: PartialEq + Send + Sync + 'static;
+
+    /// Create a [`Mutable`] which follows a resource, as with
+    /// [`MirrorResource::mirror_resource`], and also writes its value back into the resource
+    /// whenever the mutable is changed.
+    ///
+    /// Arguments:
+    /// * `get`: Computes the value of the mutable from the resource.
+    /// * `set`: Writes the value of the mutable into the resource. This is only called when the
+    ///   value differs from the result of `get`.
+    fn mirror_resource_mut<R, T>(&mut self, get: fn(&R) -> T, set: fn(&mut R, &T)) -> Mutable<T>
+    where
+        R: Resource,
+        T: PartialEq + Send + Sync + 'static;
+}
+
+impl MirrorResource for World {
+    fn mirror_resource<R, T>(&mut self, map: fn(&R) -> T) -> Mutable<T>
+    where
+        R: Resource,
+        T: PartialEq + Send + Sync + 'static,
+    {
+        let cell = self.spawn(GhostNode::default()).id();
+        MirrorResourceCommand {
+            cell,
+            map,
+            set: None,
+        }
+        .apply(self);
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+
+    fn mirror_resource_mut<R, T>(&mut self, get: fn(&R) -> T, set: fn(&mut R, &T)) -> Mutable<T>
+    where
+        R: Resource,
+        T: PartialEq + Send + Sync + 'static,
+    {
+        let cell = self.spawn(GhostNode::default()).id();
+        MirrorResourceCommand {
+            cell,
+            map: get,
+            set: Some(set),
+        }
+        .apply(self);
+        // Register the observer which writes back into the resource.
+        self.flush();
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl MirrorResource for Commands<'_, '_> {
+    fn mirror_resource<R, T>(&mut self, map: fn(&R) -> T) -> Mutable<T>
+    where
+        R: Resource,
+        T: PartialEq + Send + Sync + 'static,
+    {
+        let cell = self.spawn(GhostNode::default()).id();
+        self.queue(MirrorResourceCommand {
+            cell,
+            map,
+            set: None,
+        });
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+
+    fn mirror_resource_mut<R, T>(&mut self, get: fn(&R) -> T, set: fn(&mut R, &T)) -> Mutable<T>
+    where
+        R: Resource,
+        T: PartialEq + Send + Sync + 'static,
+    {
+        let cell = self.spawn(GhostNode::default()).id();
+        self.queue(MirrorResourceCommand {
+            cell,
+            map: get,
+            set: Some(set),
+        });
+        Mutable {
+            cell,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Command which computes the initial value of a mirrored resource, and attaches the effect
+/// which keeps it up to date.
+struct MirrorResourceCommand<R, T> {
+    cell: Entity,
+    map: fn(&R) -> T,
+    set: Option<fn(&mut R, &T)>,
+}
+
+impl<R: Resource, T: PartialEq + Send + Sync + 'static> Command for MirrorResourceCommand<R, T> {
+    fn apply(self, world: &mut World) {
+        let resource = world.resource_ref::<R>();
+        let changed = resource.last_changed();
+        let value = (self.map)(&resource);
+        let map = self.map;
+        let mut cell = world.entity_mut(self.cell);
+        cell.insert((
+            MutableCell::new(value),
+            EffectCell(Arc::new(Mutex::new(MirrorResourceEffect { map, changed }))),
+        ));
+        if let Some(set) = self.set {
+            cell.observe(
+                move |trigger: Trigger<MutableChanged<T>>,
+                      cells: Query<&MutableCell<T>>,
+                      resource: Option<ResMut<R>>| {
+                    let (Ok(cell), Some(mut resource)) = (cells.get(trigger.entity()), resource)
+                    else {
+                        return;
+                    };
+                    // Skip writes which came from the resource, so that it isn't marked as
+                    // changed again.
+                    if map(&resource) != cell.0 {
+                        set(&mut resource, &cell.0);
+                    }
+                },
+            );
+        }
+    }
+}
+
+/// Effect which copies a resource into a mutable when the resource changes.
+struct MirrorResourceEffect<R, T> {
+    map: fn(&R) -> T,
+    /// The change tick of the resource when it was last copied.
+    changed: Tick,
+}
+
+impl<R: Resource, T: PartialEq + Send + Sync + 'static> AnyEffect for MirrorResourceEffect<R, T> {
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        let Some(resource) = world.get_resource_ref::<R>() else {
+            return;
+        };
+        let changed = resource.last_changed();
+        if changed != self.changed {
+            self.changed = changed;
+            let value = (self.map)(&resource);
+            let _ = world.try_write_mutable(entity, value);
+        }
+    }
+
+    fn cleanup(&self, _world: &mut DeferredWorld, _entity: Entity) {}
+}
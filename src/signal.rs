@@ -0,0 +1,151 @@
+use bevy::{
+    ecs::system::{SystemInput, SystemParamFunction, SystemParamItem},
+    prelude::*,
+};
+
+use crate::{
+    mutable::{MutableError, ReadMutable},
+    Memo, Mutable,
+};
+
+/// A reactive value which can be read from a [`Mutable`], a [`Memo`], a resource, or a
+/// constant. Widgets can accept a `Signal<T>` rather than being generic over a system type.
+///
+/// A signal can also be passed anywhere a system is expected, such as the test of a
+/// [`crate::Cond`], the value of a [`crate::Switch`], or the items of a [`crate::For`]; the
+/// system simply returns the current value of the signal.
+#[derive(Clone, Copy, Debug)]
+pub enum Signal<T> {
+    /// A signal which reads a [`Mutable`].
+    Mutable(Mutable<T>),
+    /// A signal which reads a [`Memo`].
+    Memo(Memo<T>),
+    /// A signal which always has the same value.
+    Constant(T),
+    /// A signal computed from the world, typically by reading a resource. Non-capturing closures
+    /// can be used, for example `Signal::Resource(|world| world.resource::<Score>().0)`.
+    Resource(fn(&World) -> T),
+}
+
+impl<T> Signal<T>
+where
+    T: Send + Sync + 'static,
+{
+    /// Read the value of this [`Signal`] using a mapping function, or return an error if the
+    /// underlying mutable no longer exists or the context cannot read from the world.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    /// * `f`: The mapping function.
+    pub fn try_map<R: ReadMutable, U, F: Fn(&T) -> U>(
+        self,
+        cx: &R,
+        f: F,
+    ) -> Result<U, MutableError> {
+        match self {
+            Signal::Mutable(mutable) => cx.try_read_mutable_map(&mutable, f),
+            Signal::Memo(memo) => cx.try_read_mutable_map(&memo.inner, f),
+            Signal::Constant(value) => Ok(f(&value)),
+            Signal::Resource(read) => cx.try_read_world(read).map(|value| f(&value)),
+        }
+    }
+
+    /// Read the value of this [`Signal`] using a mapping function.
+    ///
+    /// This takes `self` by value so that it is not shadowed by [`IntoSystem::map`].
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    /// * `f`: The mapping function.
+    pub fn map<R: ReadMutable, U, F: Fn(&T) -> U>(self, cx: &R, f: F) -> U {
+        self.try_map(cx, f).unwrap()
+    }
+}
+
+impl<T> Signal<T>
+where
+    T: Copy + Send + Sync + 'static,
+{
+    /// Get the value of this [`Signal`] with Copy semantics, or return an error if the
+    /// underlying mutable no longer exists or the context cannot read from the world.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_get<R: ReadMutable>(&self, cx: &R) -> Result<T, MutableError> {
+        match self {
+            Signal::Mutable(mutable) => cx.try_read_mutable(mutable),
+            Signal::Memo(memo) => cx.try_read_mutable(&memo.inner),
+            Signal::Constant(value) => Ok(*value),
+            Signal::Resource(read) => cx.try_read_world(*read),
+        }
+    }
+
+    /// Get the value of this [`Signal`] with Copy semantics.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn get<R: ReadMutable>(&self, cx: &R) -> T {
+        self.try_get(cx).unwrap()
+    }
+}
+
+impl<T> Signal<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    /// Get the value of this [`Signal`] with Clone semantics, or return an error if the
+    /// underlying mutable no longer exists or the context cannot read from the world.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn try_get_clone<R: ReadMutable>(&self, cx: &R) -> Result<T, MutableError> {
+        match self {
+            Signal::Mutable(mutable) => cx.try_read_mutable_clone(mutable),
+            Signal::Memo(memo) => cx.try_read_mutable_clone(&memo.inner),
+            Signal::Constant(value) => Ok(value.clone()),
+            Signal::Resource(read) => cx.try_read_world(*read),
+        }
+    }
+
+    /// Get the value of this [`Signal`] with Clone semantics.
+    ///
+    /// Arguments:
+    /// * `cx`: The reactive context.
+    pub fn get_clone<R: ReadMutable>(&self, cx: &R) -> T {
+        self.try_get_clone(cx).unwrap()
+    }
+}
+
+impl<T> From<Mutable<T>> for Signal<T> {
+    fn from(mutable: Mutable<T>) -> Self {
+        Signal::Mutable(mutable)
+    }
+}
+
+impl<T> From<Memo<T>> for Signal<T> {
+    fn from(memo: Memo<T>) -> Self {
+        Signal::Memo(memo)
+    }
+}
+
+/// Marker type for the system implementation of [`Signal`].
+#[doc(hidden)]
+pub struct IsSignal;
+
+/// Allows a signal to be used as a system which returns the signal's value.
+impl<T> SystemParamFunction<IsSignal> for Signal<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type In = ();
+    type Out = T;
+    type Param = &'static World;
+
+    fn run(
+        &mut self,
+        _input: <() as SystemInput>::Inner<'_>,
+        world: SystemParamItem<&'static World>,
+    ) -> T {
+        self.get_clone(world)
+    }
+}
@@ -0,0 +1,12 @@
+use crate::CreateMutable;
+
+/// A struct which can be stored as one [`Mutable`](crate::Mutable) per field, so that readers
+/// only depend on the fields they read. Derive this with `#[derive(Store)]`, which also generates
+/// the handle type, and create a store with [`CreateMutable::create_store`].
+pub trait Store: Sized {
+    /// The handle which holds the mutables for each field.
+    type Handle: Copy;
+
+    /// Split the struct into a new mutable for each field.
+    fn into_store<C: CreateMutable + ?Sized>(self, cx: &mut C) -> Self::Handle;
+}
@@ -0,0 +1,154 @@
+use std::marker::PhantomData;
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::mutable::{Mutable, MutableError, ReadMutable, WriteMutable};
+
+/// Checks that a staged write can be applied.
+type CheckFn<W> = fn(&W, Entity) -> Result<(), MutableError>;
+
+/// Triggers [`MutableChanged`](crate::MutableChanged) for a mutable which was changed by a
+/// staged write.
+type TriggerFn<W> = fn(&mut W, Entity);
+
+/// A staged write. Returns the trigger to run if the write changed the mutable.
+type WriteOp<'a, W> = Box<dyn FnOnce(&mut W) -> Result<Option<TriggerFn<W>>, MutableError> + 'a>;
+
+/// A set of writes to mutables which are applied together when the transaction commits. See
+/// [`WriteMutable::transaction`].
+///
+/// Reading through a transaction returns the values from before the transaction started; staged
+/// writes are not visible until the commit.
+pub struct Transaction<'w, 'a, W> {
+    world: &'w W,
+    staged: StagedWrites<'a, W>,
+}
+
+/// The writes staged by a [`Transaction`], detached from the world so that they can be applied.
+pub(crate) struct StagedWrites<'a, W> {
+    checks: Vec<(Entity, CheckFn<W>)>,
+    ops: Vec<WriteOp<'a, W>>,
+}
+
+impl<'w, 'a, W: ReadMutable + WriteMutable> Transaction<'w, 'a, W> {
+    pub(crate) fn new(world: &'w W) -> Self {
+        Self {
+            world,
+            staged: StagedWrites {
+                checks: Vec::new(),
+                ops: Vec::new(),
+            },
+        }
+    }
+
+    /// Stage a write of a new value. As with [`Mutable::set`], nothing happens if the value
+    /// matches the existing value at the time of the commit.
+    pub fn set<T>(&mut self, mutable: Mutable<T>, value: T)
+    where
+        T: PartialEq + Send + Sync + 'static,
+    {
+        self.staged.checks.push((mutable.id(), check::<W, T>));
+        self.staged.ops.push(Box::new(move |w: &mut W| {
+            let changed = w.try_update_mutable_cell_silently(mutable.id(), |mut cell, eq| {
+                if !eq.unwrap_or(T::eq)(&cell, &value) {
+                    *cell = value;
+                }
+            })?;
+            Ok(changed.then_some(trigger::<W, T> as TriggerFn<W>))
+        }));
+    }
+
+    /// Stage an in-place update of a mutable value.
+    pub fn update<T, F>(&mut self, mutable: Mutable<T>, updater: F)
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce(Mut<T>) + 'a,
+    {
+        self.staged.checks.push((mutable.id(), check::<W, T>));
+        self.staged.ops.push(Box::new(move |w: &mut W| {
+            let changed =
+                w.try_update_mutable_cell_silently(mutable.id(), |cell, _| updater(cell))?;
+            Ok(changed.then_some(trigger::<W, T> as TriggerFn<W>))
+        }));
+    }
+
+    pub(crate) fn into_staged(self) -> StagedWrites<'a, W> {
+        self.staged
+    }
+}
+
+impl<W: ReadMutable + WriteMutable> StagedWrites<'_, W> {
+    /// Apply the staged writes, then trigger change events for the mutables which changed. If any
+    /// of the mutables does not exist, nothing is applied.
+    pub(crate) fn commit(self, world: &mut W) -> Result<(), MutableError> {
+        for (cell, check) in &self.checks {
+            check(world, *cell)?;
+        }
+        // No observers run until every write has been applied, so the checks above still hold.
+        // Each op was staged along with the check for the same mutable. A mutable which is
+        // written more than once is only triggered once.
+        let mut triggers = Vec::new();
+        let mut triggered = HashSet::default();
+        for (op, (cell, _)) in self.ops.into_iter().zip(self.checks) {
+            if let Some(trigger) = op(world)? {
+                if triggered.insert(cell) {
+                    triggers.push((cell, trigger));
+                }
+            }
+        }
+        for (cell, trigger) in triggers {
+            trigger(world, cell);
+        }
+        Ok(())
+    }
+}
+
+/// Check that a mutable exists and has the expected type.
+fn check<W: ReadMutable, T: Send + Sync + 'static>(
+    world: &W,
+    cell: Entity,
+) -> Result<(), MutableError> {
+    world.try_read_mutable_map(
+        &Mutable::<T> {
+            cell,
+            marker: PhantomData,
+        },
+        |_| (),
+    )
+}
+
+/// Trigger the change event for a mutable of type `T`.
+fn trigger<W: WriteMutable, T: Send + Sync + 'static>(world: &mut W, cell: Entity) {
+    world.trigger_mutable_changed::<T>(cell);
+}
+
+impl<W: ReadMutable> ReadMutable for Transaction<'_, '_, W> {
+    fn try_read_mutable_map<T, U, F: Fn(&T) -> U>(
+        &self,
+        mutable: &Mutable<T>,
+        f: F,
+    ) -> Result<U, MutableError>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.world.try_read_mutable_map(mutable, f)
+    }
+
+    fn try_read_mutable_as_ref<T>(&self, mutable: &Mutable<T>) -> Result<&T, MutableError>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.world.try_read_mutable_as_ref(mutable)
+    }
+
+    fn try_read_mutable_version<T>(&self, mutable: &Mutable<T>) -> Result<u64, MutableError>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.world.try_read_mutable_version(mutable)
+    }
+
+    fn try_read_world<U>(&self, f: fn(&World) -> U) -> Result<U, MutableError> {
+        self.world.try_read_world(f)
+    }
+}
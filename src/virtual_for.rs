@@ -0,0 +1,224 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    ecs::{system::SystemId, world::DeferredWorld},
+    prelude::*,
+    ui::experimental::GhostNode,
+};
+
+use crate::{
+    effect::{AnyEffect, EffectCell, UnregisterSystemCommand},
+    mutable::WriteMutable,
+    CreateMutable, Mutable,
+};
+
+/// Row height used before a row has been measured.
+const ESTIMATED_ROW_HEIGHT: f32 = 20.;
+
+/// Virtualized list node which only builds the rows that are visible within the scrolling
+/// ancestor, plus an overscan margin. Row entities are recycled as the list scrolls: each row
+/// receives a [`Mutable`] which is updated with whatever item the row is currently showing.
+///
+/// The node reads [`ScrollPosition`] and [`ComputedNode`] from the nearest ancestor which is not
+/// a ghost node, and inserts spacers before and after the visible rows so that the total content
+/// height matches the full list.
+pub struct VirtualFor<M, Item, ItemIter, ItemFn: IntoSystem<(), ItemIter, M>, EachFn> {
+    items_fn: Option<ItemFn>,
+    item_sys: Option<SystemId<(), ItemIter>>,
+    each: EachFn,
+    row_height: Option<f32>,
+    measured_height: f32,
+    overscan: usize,
+    rows: Vec<VirtualRow<Item>>,
+    spacers: Option<(Entity, Entity)>,
+    marker: std::marker::PhantomData<M>,
+}
+
+/// A recycled row entity, and the mutable holding the item it displays.
+struct VirtualRow<Item> {
+    child: Entity,
+    item: Mutable<Item>,
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + PartialEq + Send + Sync + 'static,
+        ItemIter: IntoIterator<Item = Item> + 'static,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        EachFn: Fn(Mutable<Item>, &mut ChildBuilder) + Send + Sync + 'static,
+    > VirtualFor<M, Item, ItemIter, ItemFn, EachFn>
+{
+    /// Constructs a new virtualized list node. By default, the row height is measured from the
+    /// first visible row.
+    pub fn new(items_fn: ItemFn, each: EachFn) -> Self {
+        Self {
+            items_fn: Some(items_fn),
+            item_sys: None,
+            each,
+            row_height: None,
+            measured_height: 0.,
+            overscan: 2,
+            rows: Vec::new(),
+            spacers: None,
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets a fixed row height, in logical pixels.
+    pub fn row_height(mut self, height: f32) -> Self {
+        self.row_height = Some(height);
+        self
+    }
+
+    /// Sets the number of extra rows to build above and below the visible window.
+    pub fn overscan(mut self, rows: usize) -> Self {
+        self.overscan = rows;
+        self
+    }
+
+    /// Finish building the list, and wrap it in an [`EffectCell`] which can be spawned.
+    pub fn build(self) -> EffectCell {
+        EffectCell(Arc::new(Mutex::new(self)))
+    }
+
+    /// Returns the scroll offset and height of the viewport, in logical pixels.
+    fn viewport(world: &World, entity: Entity) -> (f32, f32) {
+        let mut ancestor = entity;
+        while let Some(parent) = world.get::<Parent>(ancestor) {
+            ancestor = parent.get();
+            if !world.entity(ancestor).contains::<GhostNode>() {
+                let offset = world
+                    .get::<ScrollPosition>(ancestor)
+                    .map(|scroll| scroll.offset_y)
+                    .unwrap_or(0.);
+                let height = world
+                    .get::<ComputedNode>(ancestor)
+                    .map(|node| node.size().y)
+                    .unwrap_or(0.);
+                return (offset, height);
+            }
+        }
+        (0., 0.)
+    }
+
+    /// Returns the height of the first row's content, in logical pixels.
+    fn measure(&self, world: &World) -> Option<f32> {
+        let row = self.rows.first()?;
+        world
+            .get::<Children>(row.child)?
+            .iter()
+            .find_map(|child| world.get::<ComputedNode>(*child))
+            .map(|node| node.size().y)
+            .filter(|height| *height > 0.)
+    }
+
+    /// Spawn a ghost node for a new row and build its children.
+    fn build_row(&self, world: &mut World, item: &Item) -> VirtualRow<Item> {
+        let child = world.spawn(GhostNode::default()).id();
+        let mut cell = None;
+        world.commands().entity(child).with_children(|builder| {
+            let item = builder.create_mutable(item.clone());
+            (self.each)(item, builder);
+            cell = Some(item);
+        });
+        VirtualRow {
+            child,
+            item: cell.unwrap(),
+        }
+    }
+}
+
+/// Set the height of a spacer node, if it has changed.
+fn set_spacer_height(world: &mut World, spacer: Entity, height: f32) {
+    let mut node = world.get_mut::<Node>(spacer).unwrap();
+    if node.height != Val::Px(height) {
+        node.height = Val::Px(height);
+    }
+}
+
+impl<
+        M: Send + Sync + 'static,
+        Item: Clone + PartialEq + Send + Sync + 'static,
+        ItemIter: IntoIterator<Item = Item> + 'static,
+        ItemFn: IntoSystem<(), ItemIter, M> + Send + Sync + 'static,
+        EachFn: Fn(Mutable<Item>, &mut ChildBuilder) + Send + Sync + 'static,
+    > AnyEffect for VirtualFor<M, Item, ItemIter, ItemFn, EachFn>
+{
+    fn update(&mut self, world: &mut World, entity: Entity) {
+        // The first time we run, we need to register the one-shot system.
+        if let Some(items_fn) = self.items_fn.take() {
+            self.item_sys = Some(world.register_system(items_fn));
+        }
+
+        let Some(items_sys) = self.item_sys else {
+            return;
+        };
+
+        let items: Vec<Item> = match world.run_system(items_sys) {
+            Ok(items) => items.into_iter().collect(),
+            Err(_) => Vec::default(),
+        };
+
+        if self.row_height.is_none() {
+            if let Some(height) = self.measure(world) {
+                self.measured_height = height;
+            }
+        }
+        let row_height = match self.row_height {
+            Some(height) => height,
+            None if self.measured_height > 0. => self.measured_height,
+            None => ESTIMATED_ROW_HEIGHT,
+        };
+
+        // Work out which rows are visible.
+        let (offset, viewport) = Self::viewport(world, entity);
+        let first = ((offset / row_height).floor() as usize)
+            .saturating_sub(self.overscan)
+            .min(items.len());
+        let last = (((offset + viewport) / row_height).ceil() as usize + self.overscan)
+            .min(items.len())
+            .max(first);
+        let count = last - first;
+
+        // Grow or shrink the pool of recycled rows.
+        while self.rows.len() > count {
+            let row = self.rows.pop().unwrap();
+            world.entity_mut(row.child).despawn_recursive();
+        }
+        if self.rows.len() < count {
+            while self.rows.len() < count {
+                let row = self.build_row(world, &items[first + self.rows.len()]);
+                self.rows.push(row);
+            }
+            world.flush();
+        }
+
+        let (top, bottom) = *self.spacers.get_or_insert_with(|| {
+            let spacer = Node {
+                flex_shrink: 0.,
+                ..default()
+            };
+            (world.spawn(spacer.clone()).id(), world.spawn(spacer).id())
+        });
+        set_spacer_height(world, top, first as f32 * row_height);
+        set_spacer_height(world, bottom, (items.len() - last) as f32 * row_height);
+
+        // Each item is assigned to a row based on its index, so that scrolling only updates
+        // the rows which enter or leave the window.
+        let mut children = Vec::with_capacity(count + 2);
+        children.push(top);
+        for (index, item) in items.iter().enumerate().take(last).skip(first) {
+            let row = &self.rows[index % count];
+            world.write_mutable(row.item.id(), item.clone());
+            children.push(row.child);
+        }
+        children.push(bottom);
+        world.entity_mut(entity).replace_children(&children);
+    }
+
+    fn cleanup(&self, world: &mut DeferredWorld, _entity: Entity) {
+        if let Some(items_sys) = self.item_sys {
+            world.commands().queue(UnregisterSystemCommand(items_sys));
+        }
+    }
+}
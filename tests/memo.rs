@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+use do_you_believe::*;
+
+#[derive(Resource)]
+struct Score(u32);
+
+#[test]
+fn memo_waits_for_missing_resource() {
+    let mut app = App::new();
+    app.add_plugins(EffectPlugin);
+    let world = app.world_mut();
+    let memo = world.create_memo(|score: Res<Score>| score.0, |score| score * 2);
+    assert!(memo.try_get(world).is_err());
+
+    app.update();
+    assert!(memo.try_get(app.world()).is_err());
+
+    app.insert_resource(Score(2));
+    app.update();
+    assert_eq!(memo.get(app.world()), 4);
+
+    app.world_mut().resource_mut::<Score>().0 = 3;
+    app.update();
+    assert_eq!(memo.get(app.world()), 6);
+}
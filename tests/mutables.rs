@@ -0,0 +1,48 @@
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use do_you_believe::*;
+
+#[test]
+fn mutables_read_and_write() {
+    let mut world = World::new();
+    let count = world.create_mutable(1u32);
+    world
+        .run_system_once(move |mut mutables: Mutables<u32>| {
+            let value = count.get(&mutables);
+            count.set(&mut mutables, value + 1);
+        })
+        .unwrap();
+    assert_eq!(count.get(&world), 2);
+}
+
+#[test]
+fn mutables_cannot_read_resource_signals() {
+    #[derive(Resource)]
+    struct Score(u32);
+
+    let mut world = World::new();
+    world.insert_resource(Score(3));
+    let signal: Signal<u32> = Signal::Resource(|world| world.resource::<Score>().0);
+    assert_eq!(signal.try_get(&world), Ok(3));
+    let result = world
+        .run_system_once(move |mutables: Mutables<u32>| signal.try_get(&mutables))
+        .unwrap();
+    assert_eq!(result, Err(MutableError::NoWorld));
+}
+
+#[test]
+fn scope_despawns_its_mutables() {
+    let mut world = World::new();
+    let scope = world.spawn(MutableScope).id();
+    let a = world.create_mutable_in(scope, 1);
+    let b = world.create_mutable_in(scope, 2);
+    world.despawn(scope);
+    world.flush();
+    assert!(world.get_entity(a.id()).is_err());
+    assert!(world.get_entity(b.id()).is_err());
+
+    let scope = world.spawn(MutableScope).id();
+    let c = world.create_mutable_in(scope, 3);
+    world.entity_mut(scope).despawn_recursive();
+    world.flush();
+    assert!(world.get_entity(c.id()).is_err());
+}
@@ -0,0 +1,136 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use do_you_believe::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct Settings {
+    volume: u32,
+    name: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: 1,
+            name: "player".into(),
+        }
+    }
+}
+
+/// Returns an empty directory for a test.
+fn directory(name: &str) -> PathBuf {
+    let directory =
+        std::env::temp_dir().join(format!("do-you-believe-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+fn persist_app(directory: &Path, debounce: Duration) -> App {
+    let mut app = App::new();
+    app.add_plugins(PersistPlugin {
+        directory: directory.to_path_buf(),
+        debounce,
+    });
+    app
+}
+
+fn persisted(app: &mut App) -> Mutable<Settings> {
+    let world = app.world_mut();
+    let settings = world.create_mutable(Settings::default());
+    settings.persist(&mut world.commands(), "settings");
+    world.flush();
+    settings
+}
+
+fn changed() -> Settings {
+    Settings {
+        volume: 7,
+        name: "saved".into(),
+    }
+}
+
+#[test]
+fn persist_round_trip() {
+    let directory = directory("round-trip");
+    let mut app = persist_app(&directory, Duration::ZERO);
+    let settings = persisted(&mut app);
+    settings.set_clone(app.world_mut(), changed());
+    app.update();
+    assert!(directory.join("settings.ron").exists());
+
+    let mut app = persist_app(&directory, Duration::ZERO);
+    let settings = persisted(&mut app);
+    assert_eq!(settings.get_clone(app.world_mut()), changed());
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn persist_debounces_until_exit() {
+    let directory = directory("debounce");
+    let mut app = persist_app(&directory, Duration::from_secs(3600));
+    let settings = persisted(&mut app);
+    settings.set_clone(app.world_mut(), changed());
+    app.update();
+    assert!(!directory.join("settings.ron").exists());
+
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+    assert!(directory.join("settings.ron").exists());
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn persist_saves_on_despawn() {
+    let directory = directory("despawn");
+    let mut app = persist_app(&directory, Duration::from_secs(3600));
+    let settings = persisted(&mut app);
+    settings.set_clone(app.world_mut(), changed());
+    app.world_mut().despawn(settings.id());
+
+    let mut app = persist_app(&directory, Duration::ZERO);
+    let settings = persisted(&mut app);
+    assert_eq!(settings.get_clone(app.world_mut()), changed());
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn persist_fills_in_missing_fields() {
+    let directory = directory("missing-field");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("settings.ron"), "(volume: 5)").unwrap();
+    let mut app = persist_app(&directory, Duration::ZERO);
+    let settings = persisted(&mut app);
+    assert_eq!(
+        settings.get_clone(app.world_mut()),
+        Settings {
+            volume: 5,
+            name: "player".into(),
+        }
+    );
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+#[test]
+fn persist_backs_up_unreadable_file() {
+    let directory = directory("unreadable");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("settings.ron"), "not ron").unwrap();
+    let mut app = persist_app(&directory, Duration::ZERO);
+    let settings = persisted(&mut app);
+    assert_eq!(settings.get_clone(app.world_mut()), Settings::default());
+    assert_eq!(
+        std::fs::read_to_string(directory.join("settings.ron.bak")).unwrap(),
+        "not ron"
+    );
+
+    settings.set_clone(app.world_mut(), changed());
+    app.update();
+    assert!(directory.join("settings.ron").exists());
+    assert!(directory.join("settings.ron.bak").exists());
+    let _ = std::fs::remove_dir_all(&directory);
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use do_you_believe::*;
+
+#[derive(Store, Clone, PartialEq, Debug)]
+struct Player {
+    name: String,
+    score: u32,
+}
+
+/// A field which is neither `Clone` nor `PartialEq` still allows the derive.
+struct Handle(#[allow(dead_code)] u32);
+
+#[derive(Store)]
+struct Session {
+    id: u32,
+    handle: Handle,
+}
+
+#[test]
+fn store_fields() {
+    let mut world = World::new();
+    let player = world.create_store(Player {
+        name: "Ann".into(),
+        score: 3,
+    });
+    assert_eq!(player.name().get_clone(&mut world), "Ann");
+    assert_eq!(player.score().get(&world), 3);
+
+    player.score().set(&mut world, 4);
+    assert_eq!(
+        player.get(&world),
+        Player {
+            name: "Ann".into(),
+            score: 4
+        }
+    );
+}
+
+#[test]
+fn store_set_only_changes_fields_which_differ() {
+    let mut world = World::new();
+    let player = world.create_store(Player {
+        name: "Ann".into(),
+        score: 3,
+    });
+    let name_version = player.name().version(&world);
+    let score_version = player.score().version(&world);
+    player.set(
+        &mut world,
+        Player {
+            name: "Ann".into(),
+            score: 5,
+        },
+    );
+    assert_eq!(player.name().version(&world), name_version);
+    assert_ne!(player.score().version(&world), score_version);
+    assert_eq!(player.score().get(&world), 5);
+}
+
+#[test]
+fn store_missing_field() {
+    let mut world = World::new();
+    let player = world.create_store(Player {
+        name: "Ann".into(),
+        score: 3,
+    });
+    world.despawn(player.score().id());
+    assert_eq!(
+        player.try_get(&world),
+        Err(MutableError::NoEntity(player.score().id()))
+    );
+}
+
+#[test]
+fn store_with_opaque_field() {
+    let mut world = World::new();
+    let session = world.create_store(Session {
+        id: 1,
+        handle: Handle(7),
+    });
+    session.id().set(&mut world, 2);
+    assert_eq!(session.id().get(&world), 2);
+    assert_eq!(world.read_mutable_as_ref(&session.handle()).0, 7);
+}
@@ -0,0 +1,66 @@
+use bevy::{ecs::world::DeferredWorld, prelude::*};
+use do_you_believe::*;
+
+#[derive(Resource, Default)]
+struct Seen(Vec<(i32, i32)>);
+
+#[test]
+fn observers_run_after_all_writes() {
+    let mut world = World::new();
+    world.init_resource::<Seen>();
+    let a = world.create_mutable(0);
+    let b = world.create_mutable(0);
+    for mutable in [a, b] {
+        world.entity_mut(mutable.id()).observe(
+            move |_trigger: Trigger<MutableChanged<i32>>, mut world: DeferredWorld| {
+                let values = (a.get(&world), b.get(&world));
+                world.resource_mut::<Seen>().0.push(values);
+            },
+        );
+    }
+    world.flush();
+
+    world
+        .transaction(|tx| {
+            tx.set(a, 1);
+            tx.set(b, 2);
+        })
+        .unwrap();
+    assert_eq!(world.resource::<Seen>().0, vec![(1, 2), (1, 2)]);
+}
+
+#[test]
+fn missing_mutable_applies_nothing() {
+    let mut world = World::new();
+    let a = world.create_mutable(0);
+    let dead = world.create_mutable(0);
+    world.despawn(dead.id());
+    let result = world.transaction(|tx| {
+        tx.set(a, 1);
+        tx.set(dead, 1);
+    });
+    assert_eq!(result, Err(MutableError::NoEntity(dead.id())));
+    assert_eq!(a.get(&world), 0);
+}
+
+#[test]
+fn repeated_writes_trigger_once() {
+    let mut world = World::new();
+    world.init_resource::<Seen>();
+    let a = world.create_mutable(0);
+    world.entity_mut(a.id()).observe(
+        move |_trigger: Trigger<MutableChanged<i32>>, mut world: DeferredWorld| {
+            let value = a.get(&world);
+            world.resource_mut::<Seen>().0.push((value, 0));
+        },
+    );
+    world.flush();
+
+    world
+        .transaction(|tx| {
+            tx.set(a, 1);
+            tx.update(a, |mut value| *value += 1);
+        })
+        .unwrap();
+    assert_eq!(world.resource::<Seen>().0, vec![(2, 0)]);
+}